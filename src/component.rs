@@ -1,45 +1,138 @@
-use std::str::FromStr;
+use std::borrow::Cow;
 use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process;
+use std::str::{self, FromStr};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
+use indexmap::IndexMap;
+#[cfg(feature = "serde")] use serde::{Deserialize, Serialize};
+
+use encoding::Charset;
+use icalendar::Attendee;
+use param::{escape_param_value_cow, Parameters};
 use property::Property;
-use parser::{Parser, ParseErrorReason};
+use merge::{self, MergeStrategy, MergeSummary};
+use parser::Parser;
+use validate;
 
 use error::*;
 
-#[derive(Clone, Debug)]
+/// A component such as a `VCARD` or `VEVENT`, with its properties and subcomponents.
+///
+/// Two components are equal if they have the same name, the same properties and the same
+/// subcomponents in the same order. The order of differently named properties does not matter,
+/// but that of same-named properties, such as several `TEL`s, does.
+///
+/// With the `serde` feature, components can be serialized as plain structs. This is unrelated to
+/// jCal, see the `jcal` module for that.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Component {
-    /// The name of the component, such as `VCARD` or `VEVENT`.
+    /// The name of the component, such as `VCARD` or `VEVENT`. Always uppercase when created
+    /// through `Component::new` or the parser.
     pub name: String,
 
-    /// The component's properties.
-    pub props: BTreeMap<String, Vec<Property>>,
+    /// The component's properties, keyed by uppercased property name.
+    ///
+    /// Keys are kept in the order in which they were first inserted, which for parsed components
+    /// is the order in which they first appear in the input. The properties themselves keep
+    /// their name as it was spelled.
+    pub props: IndexMap<String, Vec<Property>>,
 
     /// The component's child- or sub-components.
     pub subcomponents: Vec<Component>
 }
 
 impl Component {
+    /// Create an empty component. The name is normalized to uppercase.
     pub fn new<N: Into<String>>(name: N) -> Component {
+        let mut name = name.into();
+        name.make_ascii_uppercase();
+
         Component {
-            name: name.into(),
-            props: BTreeMap::new(),
+            name,
+            props: IndexMap::new(),
             subcomponents: vec![]
         }
     }
 
+    /// Create an empty component with a newly generated `UID`, see `Component::ensure_uid()`.
+    #[cfg(feature = "uidgeneration")]
+    pub fn new_with_uid<N: Into<String>>(name: N) -> Component {
+        let mut c = Component::new(name);
+        c.ensure_uid();
+        c
+    }
+
+    /// Add a `UID` property made of a random (version 4) UUID and the domain `localhost`, unless
+    /// the component already has one. Returns whether a `UID` was added.
+    #[cfg(feature = "uidgeneration")]
+    pub fn ensure_uid(&mut self) -> bool {
+        self.ensure_uid_with_domain("localhost")
+    }
+
+    /// Like `Component::ensure_uid()`, with the given domain after the `@`.
+    #[cfg(feature = "uidgeneration")]
+    pub fn ensure_uid_with_domain(&mut self, domain: &str) -> bool {
+        if !self.get_all("UID").is_empty() {
+            return false;
+        }
+        let uid = format!("{}@{}", ::uuid::Uuid::new_v4().hyphenated(), domain);
+        self.push(Property::new("UID", uid));
+        true
+    }
+
+    /// Helper for `ComponentBuilder::new()`
+    pub fn builder<N: Into<String>>(name: N) -> ComponentBuilder {
+        ComponentBuilder::new(name)
+    }
+
     /// Append the given property, preserve other same-named properties.
     pub fn push(&mut self, prop: Property) {
-        self.props.entry(prop.name.clone()).or_insert_with(Vec::new).push(prop);
+        self.props.entry(normalize_name(&prop.name).into_owned()).or_default().push(prop);
+    }
+
+    /// Set the given property, remove other same-named properties. Returns the removed
+    /// properties, if any.
+    ///
+    /// If properties with that name already exist, the new one takes their place in the property
+    /// order.
+    pub fn set(&mut self, prop: Property) -> Option<Vec<Property>> {
+        self.props.insert(normalize_name(&prop.name).into_owned(), vec![prop])
+    }
+
+    /// Shorthand for `set(Property::new(name, value))`, where `value` is unescaped.
+    pub fn set_prop_value<N, V>(&mut self, name: N, value: V) -> Option<Vec<Property>>
+        where N: Into<String>,
+              V: AsRef<str>
+    {
+        self.set(Property::new(name, value))
     }
 
-    /// Set the given property, remove other same-named properties.
-    pub fn set(&mut self, prop: Property) {
-        self.props.insert(prop.name.clone(), vec![prop]);
+    /// Set the `GEO` property to the given latitude and longitude, replacing any existing one.
+    ///
+    /// A vCard 4.0 gets a `geo:` URI such as `geo:37.386,-122.082`, anything else the
+    /// `37.386;-122.082` form of iCalendar and vCard 3.0.
+    pub fn set_geo(&mut self, lat: f64, lon: f64) -> Option<Vec<Property>> {
+        let mut prop = Property::new("GEO", "");
+        prop.raw_value = if self.version() == Some(Version::V4_0) {
+            format!("geo:{},{}", lat, lon)
+        } else {
+            format!("{};{}", lat, lon)
+        };
+        self.set(prop)
     }
 
     /// Retrieve one property by key. Returns `None` if not exactly one property was found.
+    ///
+    /// Like all other lookups by property name, this is case-insensitive.
     pub fn get_only<P: AsRef<str>>(&self, name: P) -> Option<&Property> {
-        match self.props.get(name.as_ref()) {
+        match self.props.get(&*normalize_name(name.as_ref())) {
             Some(x) if x.len() == 1 => Some(&x[0]),
             _ => None
         }
@@ -47,16 +140,198 @@ impl Component {
 
     /// Retrieve properties by key. Returns an empty slice if key doesn't exist.
     pub fn get_all<P: AsRef<str>>(&self, name: P) -> &[Property] {
-        static EMPTY: &'static [Property] = &[];
-        match self.props.get(name.as_ref()) {
+        static EMPTY: &[Property] = &[];
+        match self.props.get(&*normalize_name(name.as_ref())) {
             Some(values) => &values[..],
             None => EMPTY
         }
     }
 
+    /// Whether there is at least one property with the given name.
+    pub fn has_prop<P: AsRef<str>>(&self, name: P) -> bool {
+        !self.get_all(name).is_empty()
+    }
+
+    /// All properties in the given group, such as the `TEL` and `X-ABLabel` of `item1.TEL` and
+    /// `item1.X-ABLabel`, in property order. Group names are matched case-insensitively.
+    pub fn props_in_group(&self, group: &str) -> Vec<&Property> {
+        self.props.values()
+            .flatten()
+            .filter(|p| p.prop_group.as_ref().is_some_and(|g| g.eq_ignore_ascii_case(group)))
+            .collect()
+    }
+
+    /// The distinct group names used by the properties, in property order. Names that differ
+    /// only in case are listed once, as first spelled.
+    pub fn groups(&self) -> Vec<&str> {
+        let mut rv: Vec<&str> = vec![];
+        for group in self.props.values().flatten().filter_map(|p| p.prop_group.as_deref()) {
+            if !rv.iter().any(|g| g.eq_ignore_ascii_case(group)) {
+                rv.push(group);
+            }
+        }
+        rv
+    }
+
+    /// The number of properties, counting each of several same-named properties.
+    pub fn prop_count(&self) -> usize {
+        self.props.values().map(Vec::len).sum()
+    }
+
+    /// Whether the component has neither properties nor subcomponents.
+    pub fn is_empty(&self) -> bool {
+        self.prop_count() == 0 && self.subcomponents.is_empty()
+    }
+
+    /// A normalized serialization for deduplication and hashing: two components that differ
+    /// only in the following ways give the same string.
+    ///
+    /// * Component, property, parameter and group names are uppercased.
+    /// * Properties are sorted by name, then group, parameters and value. Same-named parameters
+    ///   are merged, and the values of each parameter are sorted.
+    /// * Value escapes are normalized: `\N` is written as `\n`, and a backslash that does not
+    ///   start an escape is written as `\\`. Otherwise the value is kept as it is, since
+    ///   whether `,` and `;` separate items depends on the property.
+    /// * Subcomponents are canonicalized themselves and sorted by their canonical string.
+    /// * Lines are not folded and end in `\r\n`; parameter values are escaped as described in
+    ///   RFC 6868.
+    ///
+    /// This does not depend on any `WriteOptions`. The result is valid vCard or iCalendar and
+    /// parses back into an equivalent component.
+    pub fn canonical_string(&self) -> String {
+        self.canonicalized().1
+    }
+
+    /// The canonical form of this component along with its canonical string.
+    fn canonicalized(&self) -> (Component, String) {
+        let mut props = self.props.values()
+            .flatten()
+            .map(|prop| {
+                let mut params = Parameters::new();
+                for (key, values) in &prop.params {
+                    params.entry(key.to_ascii_uppercase()).or_default().extend(values.iter().cloned());
+                }
+                for values in params.values_mut() {
+                    values.sort();
+                }
+                Property {
+                    name: prop.name.to_ascii_uppercase(),
+                    params,
+                    raw_value: canonical_escapes(&prop.raw_value),
+                    prop_group: prop.prop_group.as_ref().map(|g| g.to_ascii_uppercase()),
+                    param_breaks: BTreeMap::new(),
+                    source_span: None,
+                }
+            })
+            .collect::<Vec<_>>();
+        props.sort_by(|a, b| {
+            (&a.name, &a.prop_group, &a.params, &a.raw_value)
+                .cmp(&(&b.name, &b.prop_group, &b.params, &b.raw_value))
+        });
+
+        let mut subcomponents = self.subcomponents.iter()
+            .map(Component::canonicalized)
+            .collect::<Vec<_>>();
+        subcomponents.sort_by(|a, b| a.1.cmp(&b.1));
+
+        let mut rv = Component::new(self.name.to_ascii_uppercase());
+        for prop in props {
+            rv.push(prop);
+        }
+        rv.subcomponents = subcomponents.into_iter().map(|(c, _)| c).collect();
+        let options = WriteOptions { fold_width: None, ..WriteOptions::default() };
+        let s = write_component_with(&rv, &options);
+        (rv, s)
+    }
+
+    /// Retrieve the unescaped value of one property by key. Returns `None` if not exactly one
+    /// property was found.
+    pub fn get_value<P: AsRef<str>>(&self, name: P) -> Option<String> {
+        self.get_only(name).map(Property::value_as_string)
+    }
+
+    /// Retrieve the unescaped values of all properties with the given key, in order.
+    pub fn get_all_values<P: AsRef<str>>(&self, name: P) -> Vec<String> {
+        self.get_all(name).iter().map(Property::value_as_string).collect()
+    }
+
+    /// Retrieve the unescaped value of the property with the given key whose `LANGUAGE`
+    /// matches `lang`, compared case-insensitively. If there is none, the first such property
+    /// without a `LANGUAGE` is used, and otherwise `None` is returned.
+    pub fn get_value_localized<P: AsRef<str>>(&self, name: P, lang: &str) -> Option<String> {
+        let props = self.get_all(name);
+        props.iter()
+            .find(|p| p.language().is_some_and(|l| l.eq_ignore_ascii_case(lang)))
+            .or_else(|| props.iter().find(|p| p.language().is_none()))
+            .map(Property::value_as_string)
+    }
+
+    /// Sort the properties with the given name by preference, see `Property::cmp_by_pref()`.
+    /// Properties of equal preference keep their order.
+    pub fn sort_props_by_pref<P: AsRef<str>>(&mut self, name: P) {
+        if let Some(props) = self.props.get_mut(&*normalize_name(name.as_ref())) {
+            props.sort_by(Property::cmp_by_pref);
+        }
+    }
+
+    /// Remove duplicate properties, keeping the first of each.
+    ///
+    /// Properties are duplicates if they have the same name, group, parameters and unescaped
+    /// value. Parameter names and groups are compared case-insensitively, the values of a
+    /// parameter as a set, and the values of parameters with predefined values, such as `TYPE`
+    /// or `ENCODING`, also case-insensitively, so `EMAIL;TYPE=WORK,home` duplicates
+    /// `EMAIL;type=HOME;type=work`.
+    pub fn dedup_props(&mut self) {
+        for props in self.props.values_mut() {
+            let mut seen = vec![];
+            props.retain(|p| {
+                let key = dedup_key(p);
+                let new = !seen.contains(&key);
+                if new {
+                    seen.push(key);
+                }
+                new
+            });
+        }
+    }
+
+    /// Iterate over all properties together with their (uppercase) key.
+    ///
+    /// Properties come in the order of `props`: grouped by name, with the names in the order
+    /// they first appeared, and same-named properties in document order.
+    pub fn iter_props(&self) -> impl Iterator<Item = (&str, &Property)> {
+        self.props
+            .iter()
+            .flat_map(|(key, props)| props.iter().map(move |p| (&key[..], p)))
+    }
+
+    /// Iterate over the properties of this component and all its subcomponents, each with the
+    /// component it belongs to and its (uppercase) key.
+    ///
+    /// The tree is walked depth-first: first the properties of a component, in the order of
+    /// `Component::iter_props()`, then each of its subcomponents in order, with all of its
+    /// descendants, before the next subcomponent.
+    pub fn walk(&self) -> impl Iterator<Item = (&Component, &str, &Property)> {
+        let subcomponents = self.subcomponents.iter().flat_map(|c| {
+            Box::new(c.walk()) as Box<dyn Iterator<Item = (&Component, &str, &Property)>>
+        });
+        self.iter_props()
+            .map(move |(key, prop)| (self, key, prop))
+            .chain(subcomponents)
+    }
+
+    /// Mutable variant of `Component::iter_props()`.
+    ///
+    /// Changing the name of a property through this does not move it to a different key.
+    pub fn iter_props_mut(&mut self) -> impl Iterator<Item = (&str, &mut Property)> {
+        self.props
+            .iter_mut()
+            .flat_map(|(key, props)| props.iter_mut().map(move |p| (&key[..], p)))
+    }
+
     /// Remove a single property.
     pub fn pop<P: AsRef<str>>(&mut self, name: P) -> Option<Property> {
-        match self.props.get_mut(name.as_ref()) {
+        match self.props.get_mut(&*normalize_name(name.as_ref())) {
             Some(values) => values.pop(),
             None => None
         }
@@ -64,32 +339,501 @@ impl Component {
 
     /// Remove all properties
     pub fn remove<P: AsRef<str>>(&mut self, name: P) -> Option<Vec<Property>> {
-        self.props.remove(name.as_ref())
+        self.props.shift_remove(&*normalize_name(name.as_ref()))
+    }
+
+    /// Rename all properties named `from` to `to`, keeping their parameters, values and groups,
+    /// and return how many were renamed. Names are matched case-insensitively, and the
+    /// properties are written with `to` as given.
+    ///
+    /// If properties named `to` exist already, the renamed ones are appended to them. Otherwise
+    /// they keep the position of `from` in the property order.
+    pub fn rename_prop(&mut self, from: &str, to: &str) -> usize {
+        let from_key = normalize_name(from);
+        let (index, _, mut props) = match self.props.shift_remove_full(&*from_key) {
+            Some(entry) => entry,
+            None => return 0,
+        };
+        for prop in &mut props {
+            prop.name = to.to_owned();
+        }
+
+        let count = props.len();
+        let to_key = normalize_name(to).into_owned();
+        match self.props.get_mut(&to_key) {
+            Some(existing) => existing.extend(props),
+            None => { self.props.shift_insert(index, to_key, props); },
+        }
+        count
+    }
+
+    /// Remove the property at `index` among the same-named properties. Returns `None` if there
+    /// is no such property.
+    ///
+    /// When the last property of that name is removed, the name is dropped from `props` as well.
+    pub fn remove_single_prop<P: AsRef<str>>(&mut self, name: P, index: usize) -> Option<Property> {
+        let key = normalize_name(name.as_ref());
+        let (prop, now_empty) = {
+            let values = self.props.get_mut(&*key)?;
+            if index >= values.len() {
+                return None;
+            }
+            (values.remove(index), values.is_empty())
+        };
+
+        if now_empty {
+            self.props.shift_remove(&*key);
+        }
+        Some(prop)
+    }
+
+    /// Remove all properties for which `f`, called with the (uppercase) key and the property,
+    /// returns false. Names left without properties are dropped from `props`.
+    pub fn retain_props<F: FnMut(&str, &Property) -> bool>(&mut self, mut f: F) {
+        self.props.retain(|key, props| {
+            props.retain(|p| f(key, p));
+            !props.is_empty()
+        });
+    }
+
+    /// Like `Component::retain_props()`, but also filters the properties of all subcomponents,
+    /// recursively.
+    pub fn retain_props_recursive<F: FnMut(&str, &Property) -> bool>(&mut self, mut f: F) {
+        retain_props_recursive(self, &mut f);
+    }
+
+    /// Append a subcomponent.
+    pub fn add_subcomponent(&mut self, c: Component) {
+        self.subcomponents.push(c);
+    }
+
+    /// Chainable variant of `Component::add_subcomponent()`.
+    pub fn with_subcomponent(mut self, c: Component) -> Self {
+        self.subcomponents.push(c);
+        self
+    }
+
+    /// Retrieve the direct subcomponents with the given name, compared case-insensitively.
+    pub fn all_subcomponents<N: AsRef<str>>(&self, name: N) -> Vec<&Component> {
+        let name = name.as_ref();
+        self.subcomponents
+            .iter()
+            .filter(|c| c.name.eq_ignore_ascii_case(name))
+            .collect()
+    }
+
+    /// Count the direct subcomponents for which `pred` returns `true`.
+    pub fn count_subcomponents<F: Fn(&Component) -> bool>(&self, pred: F) -> usize {
+        self.subcomponents.iter().filter(|c| pred(c)).count()
+    }
+
+    /// Count the subcomponents at any depth for which `pred` returns `true`. The component
+    /// itself is not counted.
+    pub fn count_subcomponents_recursive<F: Fn(&Component) -> bool>(&self, pred: F) -> usize {
+        fn inner<F: Fn(&Component) -> bool>(c: &Component, pred: &F) -> usize {
+            c.subcomponents.iter().map(|sub| pred(sub) as usize + inner(sub, pred)).sum()
+        }
+        inner(self, &pred)
+    }
+
+    /// Iterate mutably over the direct subcomponents with the given name, compared
+    /// case-insensitively, such as every `VEVENT` of a `VCALENDAR`.
+    pub fn subcomponents_mut<N: AsRef<str>>(&mut self, name: N) -> impl Iterator<Item = &mut Component> {
+        let name = name.as_ref().to_owned();
+        self.subcomponents
+            .iter_mut()
+            .filter(move |c| c.name.eq_ignore_ascii_case(&name))
+    }
+
+    /// Retrieve all subcomponents with the given name at any depth, in depth-first order. The
+    /// component itself is not included.
+    pub fn find_subcomponents_recursive<N: AsRef<str>>(&self, name: N) -> Vec<&Component> {
+        fn inner<'a>(c: &'a Component, name: &str, rv: &mut Vec<&'a Component>) {
+            for sub in &c.subcomponents {
+                if sub.name.eq_ignore_ascii_case(name) {
+                    rv.push(sub);
+                }
+                inner(sub, name, rv);
+            }
+        }
+
+        let mut rv = vec![];
+        inner(self, name.as_ref(), &mut rv);
+        rv
+    }
+
+    /// Merge `other` into this component, returning what was changed.
+    ///
+    /// Single-valued properties are taken from `other`, and the others combined according to
+    /// `strategy`. Subcomponents of `other` are merged into those with the same name and `UID`,
+    /// and added otherwise, unless an identical one is already present.
+    pub fn merge(&mut self, other: Component, strategy: MergeStrategy) -> MergeSummary {
+        merge::merge(self, other, strategy)
+    }
+
+    /// The version of the format, read from the `VERSION` property. Returns `None` if there is
+    /// no `VERSION` or it is not a known version.
+    pub fn version(&self) -> Option<Version> {
+        let version = self.get_all("VERSION").first()?.raw_value.trim();
+        match version {
+            "2.1" => Some(Version::V2_1),
+            "3.0" => Some(Version::V3_0),
+            "4.0" => Some(Version::V4_0),
+            "2.0" => Some(Version::ICal2_0),
+            _ => None,
+        }
+    }
+
+    /// The names of the properties in this component and its subcomponents that are neither
+    /// registered iCalendar or vCard properties nor `X-` extensions, such as a misspelled
+    /// `SUMMMARY`. Each name is listed once, as first spelled, in the order of `walk()`.
+    pub fn unknown_properties(&self) -> Vec<&str> {
+        let mut rv: Vec<&str> = vec![];
+        for (_, _, prop) in self.walk() {
+            let name = &prop.name[..];
+            if !validate::is_known_property(name) && !rv.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+                rv.push(name);
+            }
+        }
+        rv
+    }
+
+    /// The `ATTENDEE`s of this component, such as a `VEVENT`, with their parameters decoded,
+    /// see `Attendee`.
+    pub fn attendees(&self) -> Vec<Attendee> {
+        self.get_all("ATTENDEE").iter().map(Attendee::from_property).collect()
+    }
+
+    /// The `ORGANIZER` of this component, decoded the same way as `Component::attendees()`.
+    /// Returns `None` if there is not exactly one.
+    pub fn organizer(&self) -> Option<Attendee> {
+        self.get_only("ORGANIZER").map(Attendee::from_property)
+    }
+
+    /// Check the required properties and the properties allowed only once for the well-known
+    /// component types of iCalendar and vCard, in this component and all its subcomponents.
+    ///
+    /// Returns every violation found. Components with other names always pass.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = vec![];
+        validate::validate(self, &mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// A builder for creating a component in one chained expression.
+///
+/// ```
+/// # #[macro_use] extern crate vobject;
+/// # use vobject::component::Component;
+/// # fn main() {
+/// let event = Component::builder("VEVENT")
+///     .prop("UID", "19970610T172345Z-AF23B2@example.com")
+///     .prop_with_params("DTSTART", "20240115T130000", parameters!("TZID" => "Europe/Berlin"))
+///     .subcomponent(Component::builder("VALARM").prop("ACTION", "DISPLAY").build())
+///     .build();
+/// assert_eq!(event.get_only("DTSTART").unwrap().get_param("TZID"), Some("Europe/Berlin"));
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ComponentBuilder(Component);
+
+impl ComponentBuilder {
+    pub fn new<N: Into<String>>(name: N) -> ComponentBuilder {
+        ComponentBuilder(Component::new(name))
+    }
+
+    /// Append a property with the given unescaped value.
+    pub fn prop<N, V>(self, name: N, value: V) -> Self
+        where N: Into<String>,
+              V: AsRef<str>
+    {
+        self.prop_with_params(name, value, Parameters::new())
+    }
+
+    /// Append a property with the given unescaped value and parameters.
+    pub fn prop_with_params<N, V>(mut self, name: N, value: V, params: Parameters) -> Self
+        where N: Into<String>,
+              V: AsRef<str>
+    {
+        let mut prop = Property::new(name, value);
+        prop.params = params;
+        self.0.push(prop);
+        self
+    }
+
+    /// Append a property as it is.
+    pub fn property(mut self, prop: Property) -> Self {
+        self.0.push(prop);
+        self
+    }
+
+    /// Append a subcomponent.
+    pub fn subcomponent(mut self, c: Component) -> Self {
+        self.0.add_subcomponent(c);
+        self
+    }
+
+    pub fn build(self) -> Component {
+        self.0
+    }
+}
+
+/// Parameters whose values are predefined keywords, which are case-insensitive.
+const CASE_INSENSITIVE_PARAMS: &[&str] = &[
+    "CHARSET", "CUTYPE", "ENCODING", "FBTYPE", "KIND", "PARTSTAT", "RANGE", "RELATED", "RELTYPE",
+    "ROLE", "RSVP", "TYPE", "VALUE",
+];
+
+/// What identifies a property for `Component::dedup_props`.
+fn dedup_key(p: &Property) -> (Option<String>, Parameters, String) {
+    let mut params = Parameters::new();
+    for (key, values) in &p.params {
+        let key = key.to_ascii_uppercase();
+        let fold_case = CASE_INSENSITIVE_PARAMS.contains(&&key[..]);
+        let values = values.iter()
+            .map(|v| if fold_case { v.to_ascii_uppercase() } else { v.clone() });
+        params.entry(key).or_default().extend(values);
+    }
+    for values in params.values_mut() {
+        values.sort();
+        values.dedup();
+    }
+    (p.prop_group.as_ref().map(|g| g.to_ascii_uppercase()), params, p.value_as_string())
+}
+
+/// Rewrite the escapes of a raw value in one canonical form, see `Component::canonical_string`.
+fn canonical_escapes(raw: &str) -> String {
+    let mut rv = String::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            rv.push(c);
+            continue;
+        }
+        match chars.peek().cloned() {
+            Some('n') | Some('N') => {
+                chars.next();
+                rv.push_str("\\n");
+            },
+            Some(x @ '\\') | Some(x @ ';') | Some(x @ ',') => {
+                chars.next();
+                rv.push('\\');
+                rv.push(x);
+            },
+            _ => rv.push_str("\\\\"),
+        }
+    }
+    rv
+}
+
+/// Property names are case-insensitive and stored in uppercase.
+fn normalize_name(name: &str) -> Cow<'_, str> {
+    if name.bytes().any(|b| b.is_ascii_lowercase()) {
+        Cow::Owned(name.to_ascii_uppercase())
+    } else {
+        Cow::Borrowed(name)
     }
 }
 
 impl FromStr for Component {
-    type Err = VObjectError;
+    type Err = ParseError;
 
     /// Same as `vobject::parse_component`
-    fn from_str(s: &str) -> VObjectResult<Component> {
+    fn from_str(s: &str) -> Result<Component, ParseError> {
         parse_component(s)
     }
 }
 
+/// Iterate over the subcomponents, taking them out of the component. The properties are
+/// dropped.
+impl IntoIterator for Component {
+    type Item = Component;
+    type IntoIter = ::std::vec::IntoIter<Component>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.subcomponents.into_iter()
+    }
+}
+
+/// Iterate over the direct subcomponents.
+impl<'a> IntoIterator for &'a Component {
+    type Item = &'a Component;
+    type IntoIter = ::std::slice::Iter<'a, Component>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.subcomponents.iter()
+    }
+}
+
+impl fmt::Display for Component {
+    /// Same as `vobject::write_component`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&write_component(self))
+    }
+}
+
 /// Parse exactly one component. Trailing data generates errors.
-pub fn parse_component(s: &str) -> VObjectResult<Component> {
-    let (rv, new_s) = read_component(s)?;
+pub fn parse_component(s: &str) -> Result<Component, ParseError> {
+    parse_component_with(s, &ParseOptions::default())
+}
+
+/// The versions of vCard and iCalendar, see `Component::version()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Version {
+    /// vCard 2.1, with bare parameters and quoted-printable values.
+    V2_1,
+    /// vCard 3.0, RFC 2426.
+    V3_0,
+    /// vCard 4.0, RFC 6350.
+    V4_0,
+    /// iCalendar 2.0, RFC 5545.
+    ICal2_0,
+}
+
+impl Version {
+    /// The value of the `VERSION` property for this version.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Version::V2_1 => "2.1",
+            Version::V3_0 => "3.0",
+            Version::V4_0 => "4.0",
+            Version::ICal2_0 => "2.0",
+        }
+    }
+}
+
+/// Parse exactly one component from bytes that need not be UTF-8, such as a vCard 2.1 from an
+/// old phone backup.
+///
+/// Content lines that are valid UTF-8 are read as they are. Any other content line is decoded
+/// with the charset given by its `CHARSET` parameter, or as Windows-1252, a superset of
+/// ISO-8859-1, if there is none or it is not known. Property values are therefore always UTF-8,
+/// while the `CHARSET` parameter is kept.
+pub fn parse_component_bytes(b: &[u8]) -> Result<Component, ParseError> {
+    parse_component(&decode_lines(b))
+}
+
+/// Read and parse exactly one component from a file, such as a `.vcf` or `.ics` file.
+///
+/// Lines may end in CRLF or LF, and a leading byte order mark is skipped. The file need not be
+/// UTF-8, see `parse_component_bytes()`. Errors are `VObjectError::Io` if the file cannot be
+/// read, and `VObjectError::Parse` otherwise.
+pub fn parse_component_from_file<P: AsRef<Path>>(path: P) -> Result<Component, VObjectError> {
+    let bytes = fs::read(path)?;
+    Ok(parse_component_bytes(&bytes)?)
+}
+
+/// Decode each logical line of `b` on its own, see `parse_component_bytes()`.
+fn decode_lines(b: &[u8]) -> Cow<'_, str> {
+    if let Ok(s) = str::from_utf8(b) {
+        return Cow::Borrowed(s);
+    }
+
+    let mut rv = String::with_capacity(b.len() + b.len() / 8);
+    let mut rest = b;
+    while !rest.is_empty() {
+        // A logical line ends at a line break that is not followed by a folding space or tab.
+        let mut end = 0;
+        loop {
+            match rest[end..].iter().position(|&c| c == b'\n') {
+                Some(i) => end += i + 1,
+                None => {
+                    end = rest.len();
+                    break;
+                },
+            }
+            if !rest[end..].starts_with(b" ") && !rest[end..].starts_with(b"\t") {
+                break;
+            }
+        }
+
+        let (line, tail) = rest.split_at(end);
+        match str::from_utf8(line) {
+            Ok(line) => rv.push_str(line),
+            Err(_) => {
+                let charset = line_charset(line)
+                    .and_then(|name| Charset::from_name(&name).ok())
+                    .filter(|&c| c != Charset::Utf8)
+                    .unwrap_or(Charset::Windows1252);
+                let decoded = charset.decode(line.to_vec())
+                    .unwrap_or_else(|_| String::from_utf8_lossy(line).into_owned());
+                rv.push_str(&decoded);
+            },
+        }
+        rest = tail;
+    }
+    Cow::Owned(rv)
+}
+
+/// The `CHARSET` parameter of a content line, read from the ASCII part before the value.
+fn line_charset(line: &[u8]) -> Option<String> {
+    let head = &line[..line.iter().position(|&c| c == b':')?];
+    let head = String::from_utf8_lossy(head);
+    head.split(';')
+        .skip(1)
+        .filter_map(|param| param.split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("CHARSET"))
+        .map(|(_, value)| value.trim().trim_matches('"').to_owned())
+}
+
+/// Options controlling how components are parsed.
+///
+/// This is the one place for parser settings: it is taken by `parse_component_with()`,
+/// `parse_components_with()` and `ComponentReader::with_options()`, and the functions without
+/// options use `ParseOptions::default()`. New settings are added as fields, so construct it
+/// with `..ParseOptions::default()`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Read bare vCard 2.1 parameters such as the `WORK` and `VOICE` in `TEL;WORK;VOICE:...` as
+    /// values of the `TYPE` parameter, as if they had been given as `TYPE=WORK,VOICE`. Only
+    /// well-known type names are treated this way, other parameters without a value are kept
+    /// as they are. Off by default.
+    pub vcard21: bool,
+
+    /// Reject input with a physical line longer than the 75 octets allowed by RFC 5545 and
+    /// RFC 6350, not counting the line ending, with `ParseErrorKind::LineTooLong`. Useful to
+    /// check the output of other writers. Off by default, so long lines are accepted.
+    pub strict_line_length: bool,
+
+    /// Record the byte range each property was read from in `Property::source_span`. Off by
+    /// default.
+    ///
+    /// The range starts at the first byte of the group or name and ends after the last byte of
+    /// the value, before the final line break. It is taken in the folded input, so for a folded
+    /// property it covers all of its physical lines, line breaks and folding whitespace
+    /// included, and replacing that range replaces exactly that property. A skipped byte order
+    /// mark still counts. Offsets are into the string being parsed, so for
+    /// `parse_component_bytes()` they refer to the decoded text, and for a `ComponentReader`
+    /// to the text of each component.
+    pub source_spans: bool,
+}
+
+/// Parse exactly one component with the given options, see `parse_component()`.
+pub fn parse_component_with(s: &str, options: &ParseOptions) -> Result<Component, ParseError> {
+    let (rv, new_s) = read_component_with(s, options)?;
     if !new_s.is_empty() {
-        return Err(ParseErrorReason::TrailingData(new_s.into()).into());
+        let kind = ParseErrorKind::TrailingData(new_s.into());
+        return Err(ParseError::at(kind, s, s.len() - new_s.len()));
     }
 
     Ok(rv)
 }
 
 /// Parse one component and return the rest of the string.
-pub fn read_component(s: &str) -> VObjectResult<(Component, &str)> {
-    let mut parser = Parser::new(s);
+pub fn read_component(s: &str) -> Result<(Component, &str), ParseError> {
+    read_component_with(s, &ParseOptions::default())
+}
+
+fn read_component_with<'s>(s: &'s str, options: &ParseOptions)
+    -> Result<(Component, &'s str), ParseError>
+{
+    let mut parser = Parser::with_options(s, options);
+    parser.check_line_lengths()?;
     let rv = parser.consume_component()?;
     let new_s = if parser.eof() {
         ""
@@ -99,85 +843,349 @@ pub fn read_component(s: &str) -> VObjectResult<(Component, &str)> {
     Ok((rv, new_s))
 }
 
-/// Write a component to a String.
-pub fn write_component(c: &Component) -> String {
-    fn inner(buf: &mut String, c: &Component) {
-        buf.push_str("BEGIN:");
-        buf.push_str(&c.name);
-        buf.push_str("\r\n");
-
-        for (prop_name, props) in &c.props {
-            for prop in props.iter() {
-                if let Some(ref x) = prop.prop_group {
-                    buf.push_str(&x);
-                    buf.push('.');
-                };
-                buf.push_str(&prop_name);
-                for (param_key, param_value) in &prop.params {
-                    buf.push(';');
-                    buf.push_str(&param_key);
-                    buf.push('=');
-                    buf.push_str(&param_value);
+/// Parse all components in a string, such as the concatenated VCARDs of an address book.
+///
+/// Whitespace and blank lines between components are ignored. An input without any components
+/// yields an empty `Vec`.
+pub fn parse_components(s: &str) -> Result<Vec<Component>, ParseError> {
+    parse_components_with(s, &ParseOptions::default())
+}
+
+/// Parse all components in a string with the given options, see `parse_components()`.
+pub fn parse_components_with(s: &str, options: &ParseOptions) -> Result<Vec<Component>, ParseError> {
+    let mut parser = Parser::with_options(s, options);
+    parser.check_line_lengths()?;
+    let mut rv = vec![];
+
+    loop {
+        parser.consume_while(char::is_whitespace);
+        if parser.eof() {
+            return Ok(rv);
+        }
+        rv.push(parser.consume_component()?);
+    }
+}
+
+/// Options controlling how components are written, see `write_component_with()`.
+///
+/// Parameters are always written sorted by name, see `Parameters`, so there is no option for
+/// their order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// The maximum length of a physical line in octets, not counting the line ending. `None`
+    /// disables folding. Defaults to 75, as recommended by RFC 5545 and RFC 6350.
+    ///
+    /// A physical line always holds at least one character, so very small widths still produce
+    /// parseable output. `BEGIN` and `END` lines are never folded.
+    pub fold_width: Option<usize>,
+
+    /// The line ending used after each content line and when folding.
+    pub line_ending: LineEnding,
+
+    /// Escape `^`, `"` and line breaks in parameter values as described in RFC 6868. On by
+    /// default; turn it off for consumers that do not understand the escapes, in which case
+    /// such characters are written as they are.
+    pub escape_param_values: bool,
+
+    /// Write a parameter that was repeated on the content line, such as `TYPE=work;TYPE=voice`,
+    /// as one comma-separated list, `TYPE=work,voice`. Off by default, which writes each
+    /// occurrence back as it was parsed, see `Property::param_breaks`.
+    pub combine_type_params: bool,
+}
+
+impl Default for WriteOptions {
+    fn default() -> WriteOptions {
+        WriteOptions {
+            fold_width: Some(75),
+            line_ending: LineEnding::CrLf,
+            escape_param_values: true,
+            combine_type_params: false,
+        }
+    }
+}
+
+/// Line endings for written components.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    /// `\r\n`, as required by the RFCs.
+    #[default]
+    CrLf,
+    /// A bare `\n`.
+    Lf,
+}
+
+impl LineEnding {
+    /// The characters written for this line ending.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::CrLf => "\r\n",
+            LineEnding::Lf => "\n",
+        }
+    }
+}
+
+/// Parse all components in a string, skipping malformed content lines instead of failing.
+///
+/// Returns the components that could be read along with every error that was skipped. A
+/// malformed property is dropped without affecting the rest of its component. An `END` that
+/// does not match closes the current component and is then matched against the enclosing ones,
+/// and a component still open at the end of the input is kept as far as it was read. Each
+/// malformed line is reported once.
+pub fn parse_components_lenient(s: &str) -> (Vec<Component>, Vec<ParseError>) {
+    let mut parser = Parser::new_lenient(s);
+    let mut rv = vec![];
+    let mut errors = vec![];
+
+    loop {
+        parser.consume_while(char::is_whitespace);
+        if parser.eof() {
+            break;
+        }
+        match parser.consume_component() {
+            Ok(c) => rv.push(c),
+            Err(e) => {
+                errors.append(parser.errors.as_mut().unwrap());
+                if !e.follows(errors.last()) {
+                    errors.push(e);
                 }
-                buf.push(':');
-                buf.push_str(&fold_line(&prop.raw_value));
-                buf.push_str("\r\n");
+                parser.skip_line();
             }
         }
+    }
+
+    errors.extend(parser.errors.unwrap_or_default());
+    (rv, errors)
+}
+
+/// Write a component to a String.
+///
+/// Properties are written in the order of `Component::props`, and the parameters of each
+/// property sorted by name, so the same component always produces the same output. Property
+/// names are written as they are spelled, so vendor extensions such as `X-ABLabel` come out
+/// unchanged.
+pub fn write_component(c: &Component) -> String {
+    write_component_with(c, &WriteOptions::default())
+}
+
+/// Write a component to a String, using the given options.
+pub fn write_component_with(c: &Component, options: &WriteOptions) -> String {
+    let mut buf = Vec::new();
+    write_with(&mut buf, c, options).expect("writing to a Vec never fails");
+    String::from_utf8(buf).expect("components are written from valid UTF-8")
+}
+
+/// Write a component to `w` without building the whole text in memory first.
+///
+/// This issues many small writes, so wrap unbuffered writers such as files or sockets in a
+/// `std::io::BufWriter`.
+pub fn write_component_to<W: Write>(w: &mut W, c: &Component) -> io::Result<()> {
+    write_with(w, c, &WriteOptions::default())
+}
+
+/// Write a component to a file with the default options, see `write_component_to_file_with()`.
+pub fn write_component_to_file<P: AsRef<Path>>(path: P, c: &Component) -> io::Result<()> {
+    write_component_to_file_with(path, c, &WriteOptions::default())
+}
 
-        for subcomponent in &c.subcomponents {
-            inner(buf, subcomponent);
+/// Write a component to a file, replacing it atomically.
+///
+/// The component is written to a newly created temporary file in the same directory, which is
+/// flushed to disk and then renamed over `path`. Every call uses its own temporary file, so
+/// concurrent writes of the same path from several threads or processes do not mix; the last
+/// rename wins. If anything fails, the temporary file is removed and an existing file at `path`
+/// is left as it was.
+pub fn write_component_to_file_with<P: AsRef<Path>>(path: P, c: &Component, options: &WriteOptions)
+    -> io::Result<()>
+{
+    static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let path = path.as_ref();
+    let file_name = path.file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+
+    // `create_new` fails instead of reusing a file left behind by an earlier process with the
+    // same id, in which case the next name is tried.
+    let (file, tmp_path) = loop {
+        let mut tmp_name = OsString::from(".");
+        tmp_name.push(file_name);
+        let n = TMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+        tmp_name.push(format!(".{}.{}.tmp", process::id(), n));
+        let tmp_path = path.with_file_name(tmp_name);
+        match fs::OpenOptions::new().write(true).create_new(true).open(&tmp_path) {
+            Ok(file) => break (file, tmp_path),
+            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
         }
+    };
 
-        buf.push_str("END:");
-        buf.push_str(&c.name);
-        buf.push_str("\r\n");
+    let mut w = io::BufWriter::new(file);
+    let result = write_with(&mut w, c, options)
+        .and_then(|()| w.into_inner().map_err(io::IntoInnerError::into_error))
+        .and_then(|file| file.sync_all())
+        .and_then(|()| fs::rename(&tmp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
     }
+    result
+}
 
-    let mut buf = String::new();
-    inner(&mut buf, c);
-    buf
+fn retain_props_recursive(c: &mut Component, f: &mut dyn FnMut(&str, &Property) -> bool) {
+    c.retain_props(&mut *f);
+    for sub in &mut c.subcomponents {
+        retain_props_recursive(sub, f);
+    }
 }
 
-/// Fold contentline to 75 bytes or less. This function assumes the input
-/// to be unfolded, which means no '\n' or '\r' in it.
-pub fn fold_line(line: &str) -> String {
-    let limit = 75;
-    let len = line.len();
-    let mut bytes_remaining = len;
-    let mut ret = String::with_capacity(len + (len / limit * 3));
+fn write_with<W: Write>(w: &mut W, c: &Component, options: &WriteOptions) -> io::Result<()> {
+    let eol = options.line_ending.as_str();
+    write!(w, "BEGIN:{}{}", c.name, eol)?;
 
-    let mut pos = 0;
-    let mut next_pos = limit;
-    while bytes_remaining > limit {
-        while line.is_char_boundary(next_pos) == false {
-            next_pos -= 1;
+    let mut line = String::new();
+    for props in c.props.values() {
+        for prop in props.iter() {
+            line.clear();
+            if let Some(ref x) = prop.prop_group {
+                line.push_str(x);
+                line.push('.');
+            };
+            line.push_str(&prop.name);
+            for (param_key, param_values) in &prop.params {
+                let occurrences = if options.combine_type_params {
+                    vec![&param_values[..]]
+                } else {
+                    prop.param_occurrences(param_key, param_values)
+                };
+                for occurrence in occurrences {
+                    line.push(';');
+                    line.push_str(param_key);
+                    for (i, param_value) in occurrence.iter().enumerate() {
+                        line.push(if i == 0 { '=' } else { ',' });
+                        push_param_value(&mut line, param_value, options.escape_param_values);
+                    }
+                }
+            }
+            line.push(':');
+            line.push_str(&prop.raw_value);
+
+            write_folded(w, &line, options.fold_width, eol)?;
+            w.write_all(eol.as_bytes())?;
         }
-        ret.push_str(&line[pos..next_pos]);
-        ret.push_str("\r\n ");
+    }
 
-        bytes_remaining -= next_pos - pos;
-        pos = next_pos;
-        next_pos += limit;
+    for subcomponent in &c.subcomponents {
+        write_with(w, subcomponent, options)?;
     }
 
-    ret.push_str(&line[len - bytes_remaining..]);
-    ret
+    write!(w, "END:{}{}", c.name, eol)
+}
+
+/// Append a single parameter value, escaped as described in RFC 6868 if `escape` is set and
+/// wrapped in DQUOTEs if it contains characters that would otherwise end the value.
+fn push_param_value(buf: &mut String, value: &str, escape: bool) {
+    let value = if escape { escape_param_value_cow(value) } else { Cow::Borrowed(value) };
+    if value.contains([':', ';', ',']) {
+        buf.push('"');
+        buf.push_str(&value);
+        buf.push('"');
+    } else {
+        buf.push_str(&value);
+    }
 }
 
+/// Write `line`, folded to `width` octets per physical line if given.
+fn write_folded<W>(w: &mut W, line: &str, width: Option<usize>, eol: &str) -> io::Result<()>
+    where W: Write
+{
+    let bytes = line.as_bytes();
+    let width = match width {
+        Some(width) => width,
+        None => return w.write_all(bytes),
+    };
+
+    let mut line_len = 0;
+    let mut chunk_start = 0;
+    for (i, c) in line.char_indices() {
+        // Every physical line gets at least one character, whatever the width.
+        if i > chunk_start && line_len + c.len_utf8() > width {
+            w.write_all(&bytes[chunk_start..i])?;
+            w.write_all(eol.as_bytes())?;
+            w.write_all(b" ")?;
+            chunk_start = i;
+            line_len = 1;
+        }
+        line_len += c.len_utf8();
+    }
+    w.write_all(&bytes[chunk_start..])
+}
+
+/// Fold contentline to 75 octets or less. This function assumes the input
+/// to be unfolded, which means no '\n' or '\r' in it.
+///
+/// The leading space of a continuation line counts towards its length, and a line is never
+/// broken inside of a multi-byte UTF-8 sequence.
+pub fn fold_line(line: &str) -> String {
+    let len = line.len();
+    let mut buf = Vec::with_capacity(len + (len / 74 * 3));
+    write_folded(&mut buf, line, Some(75), "\r\n").expect("writing to a Vec never fails");
+    String::from_utf8(buf).expect("folding keeps UTF-8 sequences intact")
+}
 
 #[cfg(test)]
 mod tests {
     use component::fold_line;
+    #[cfg(feature = "uidgeneration")] use component::Component;
 
     #[test]
     fn test_fold() {
         let line = "This should be multiple lines and fold on char boundaries. 毎害止\
                    加食下組多地将写館来局必第。東証細再記得玲祉込吉宣会法授";
         let expected = "This should be multiple lines and fold on char boundaries. 毎害止\
-                       加食\r\n 下組多地将写館来局必第。東証細再記得玲祉込吉宣会法\r\n 授";
+                       加食\r\n 下組多地将写館来局必第。東証細再記得玲祉込吉宣会\r\n 法授";
         assert_eq!(expected, fold_line(line));
         assert_eq!("ab", fold_line("ab"));
     }
 
+    #[test]
+    #[cfg(feature = "uidgeneration")]
+    fn test_ensure_uid() {
+        let mut c = Component::new_with_uid("VEVENT");
+        let uid = c.get_value("UID").unwrap();
+        let (uuid, domain) = uid.split_at(36);
+        assert_eq!(domain, "@localhost");
+        assert_eq!(uuid.as_bytes()[14], b'4');
+        assert_eq!(uuid.matches('-').count(), 4);
+
+        assert!(!c.ensure_uid());
+        assert_eq!(c.get_value("UID").unwrap(), uid);
+
+        let mut other = Component::new("VTODO");
+        assert!(other.ensure_uid_with_domain("example.com"));
+        let other_uid = other.get_value("UID").unwrap();
+        assert!(other_uid.ends_with("@example.com"));
+        assert!(other_uid[..36] != uid[..36]);
+    }
+
+    fn assert_folded(line: &str) {
+        let folded = fold_line(line);
+        for physical in folded.split("\r\n") {
+            assert!(physical.len() <= 75, "{:?} is {} octets long", physical, physical.len());
+        }
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+
+    #[test]
+    fn test_fold_octets() {
+        assert_folded(&"a".repeat(75));
+        assert_folded(&"a".repeat(76));
+        assert_folded(&"a".repeat(500));
+        assert_folded(&"毎害止加食下組多地将写館来局必第".repeat(10));
+        assert_folded(&"😀🎉👍".repeat(30));
+        assert_folded(&format!("NOTE:Ünïcödé {}", "é😀a".repeat(40)));
+
+        assert_eq!(fold_line(&"a".repeat(75)), "a".repeat(75));
+        // A four-byte character that would end past octet 75 moves to the next line as a whole.
+        let folded = fold_line(&format!("{}😀😀", "a".repeat(72)));
+        assert_eq!(folded, format!("{}\r\n 😀😀", "a".repeat(72)));
+    }
+
 }
@@ -0,0 +1,230 @@
+//! Decoders for the transfer encodings of property values.
+
+use error::DecodeError;
+
+/// Decode standard base64, as used by `ENCODING=b` and `ENCODING=BASE64`.
+///
+/// Whitespace is ignored, since vCard 2.1 writers indent continuation lines of long values, and
+/// trailing padding is optional.
+pub fn decode_base64(s: &str) -> Result<Vec<u8>, DecodeError> {
+    let mut rv = Vec::with_capacity(s.len() / 4 * 3);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    let mut padded = false;
+
+    for c in s.chars() {
+        let v = match c {
+            'A'..='Z' => c as u32 - 'A' as u32,
+            'a'..='z' => c as u32 - 'a' as u32 + 26,
+            '0'..='9' => c as u32 - '0' as u32 + 52,
+            '+' => 62,
+            '/' => 63,
+            '=' => {
+                padded = true;
+                continue;
+            },
+            c if c.is_whitespace() => continue,
+            _ => return Err(DecodeError::InvalidBase64),
+        };
+        if padded {
+            return Err(DecodeError::InvalidBase64);
+        }
+
+        acc = (acc << 6) | v;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            rv.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+
+    // A single character in the last quantum does not make up a byte.
+    if bits >= 6 {
+        return Err(DecodeError::InvalidBase64);
+    }
+    Ok(rv)
+}
+
+/// Decode quoted-printable, as used by vCard 2.1 `ENCODING=QUOTED-PRINTABLE`.
+///
+/// `=XX` sequences become the byte with that hexadecimal value, and soft line breaks (`=` at the
+/// end of a line) are removed. The parser already joins the lines of such values, but values
+/// built by hand may still contain them.
+pub fn decode_quoted_printable(s: &str) -> Result<Vec<u8>, DecodeError> {
+    let bytes = s.as_bytes();
+    let mut rv = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] != b'=' {
+            rv.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+
+        match (bytes.get(i + 1), bytes.get(i + 2)) {
+            (Some(b'\r'), Some(b'\n')) => i += 3,
+            (Some(b'\n'), _) => i += 2,
+            (Some(&hi), Some(&lo)) => {
+                match (hex_value(hi), hex_value(lo)) {
+                    (Some(hi), Some(lo)) => rv.push(hi << 4 | lo),
+                    _ => return Err(DecodeError::InvalidQuotedPrintable),
+                }
+                i += 3;
+            },
+            _ => return Err(DecodeError::InvalidQuotedPrintable),
+        }
+    }
+
+    Ok(rv)
+}
+
+/// Decode the data of a `data:` URI (RFC 2397), as used by vCard 4.0 `PHOTO` and `KEY`
+/// values, such as `data:image/png;base64,iVBORw0KGgo=`. Returns the media type, which may be
+/// empty, and the data, either base64-decoded or with `%XX` sequences decoded.
+pub fn decode_data_uri(s: &str) -> Result<(String, Vec<u8>), DecodeError> {
+    let rest = match s.get(..5) {
+        Some(scheme) if scheme.eq_ignore_ascii_case("data:") => &s[5..],
+        _ => return Err(DecodeError::InvalidDataUri),
+    };
+    let (header, data) = rest.split_once(',').ok_or(DecodeError::InvalidDataUri)?;
+
+    let (media_type, base64) = match header.rsplit_once(';') {
+        Some((media_type, ext)) if ext.eq_ignore_ascii_case("base64") => (media_type, true),
+        _ => (header, false),
+    };
+    let data = if base64 {
+        decode_base64(data)?
+    } else {
+        decode_percent(data)?
+    };
+    Ok((media_type.to_owned(), data))
+}
+
+fn decode_percent(s: &str) -> Result<Vec<u8>, DecodeError> {
+    let bytes = s.as_bytes();
+    let mut rv = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] != b'%' {
+            rv.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+        match (bytes.get(i + 1).cloned().and_then(hex_value), bytes.get(i + 2).cloned().and_then(hex_value)) {
+            (Some(hi), Some(lo)) => rv.push(hi << 4 | lo),
+            _ => return Err(DecodeError::InvalidDataUri),
+        }
+        i += 3;
+    }
+
+    Ok(rv)
+}
+
+fn hex_value(b: u8) -> Option<u8> {
+    (b as char).to_digit(16).map(|d| d as u8)
+}
+
+/// The character sets that decoded values can be converted from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Charset {
+    Utf8,
+    Latin1,
+    Windows1252,
+}
+
+/// The characters of Windows-1252 that differ from ISO-8859-1, for the bytes 0x80 to 0x9F.
+/// Bytes that Windows-1252 leaves undefined map to the same C1 control as in ISO-8859-1.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2C6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}', '\u{8F}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}', '\u{17E}', '\u{178}',
+];
+
+impl Charset {
+    /// Look up a charset by the name used in a `CHARSET` parameter, ignoring case.
+    pub fn from_name(name: &str) -> Result<Charset, DecodeError> {
+        match &*name.to_ascii_uppercase() {
+            "UTF-8" | "UTF8" | "US-ASCII" | "ASCII" => Ok(Charset::Utf8),
+            "ISO-8859-1" | "ISO8859-1" | "ISO_8859-1" | "LATIN1" | "LATIN-1" => Ok(Charset::Latin1),
+            "WINDOWS-1252" | "CP1252" => Ok(Charset::Windows1252),
+            _ => Err(DecodeError::UnknownCharset(name.to_owned())),
+        }
+    }
+
+    /// Convert bytes in this charset to a String.
+    pub fn decode(self, bytes: Vec<u8>) -> Result<String, DecodeError> {
+        match self {
+            Charset::Utf8 => String::from_utf8(bytes).map_err(|_| DecodeError::InvalidUtf8),
+            Charset::Latin1 => Ok(bytes.into_iter().map(char::from).collect()),
+            Charset::Windows1252 => Ok(bytes.into_iter().map(|b| match b {
+                0x80..=0x9F => WINDOWS_1252_HIGH[(b - 0x80) as usize],
+                b => char::from(b),
+            }).collect()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_base64, decode_data_uri, decode_quoted_printable, Charset};
+    use error::DecodeError;
+
+    #[test]
+    fn test_decode_base64() {
+        assert_eq!(decode_base64("").unwrap(), b"");
+        assert_eq!(decode_base64("Zg==").unwrap(), b"f");
+        assert_eq!(decode_base64("Zm8=").unwrap(), b"fo");
+        assert_eq!(decode_base64("Zm9v").unwrap(), b"foo");
+        assert_eq!(decode_base64("Zm9vYg").unwrap(), b"foob");
+        assert_eq!(decode_base64("Zm9v YmFy\n  ").unwrap(), b"foobar");
+        assert_eq!(decode_base64("/+8A").unwrap(), [0xff, 0xef, 0x00]);
+
+        assert_eq!(decode_base64("Zm9vY"), Err(DecodeError::InvalidBase64));
+        assert_eq!(decode_base64("Zg==Zg=="), Err(DecodeError::InvalidBase64));
+        assert_eq!(decode_base64("Zm9v!"), Err(DecodeError::InvalidBase64));
+    }
+
+    #[test]
+    fn test_decode_data_uri() {
+        let decode = |s| decode_data_uri(s).unwrap();
+        assert_eq!(decode("data:image/png;base64,Zm9v"), ("image/png".to_owned(), b"foo".to_vec()));
+        assert_eq!(decode("DATA:;BASE64,Zm9v"), ("".to_owned(), b"foo".to_vec()));
+        assert_eq!(decode("data:text/plain;charset=utf-8,K%C3%B6ln%2c"),
+                   ("text/plain;charset=utf-8".to_owned(), "Köln,".as_bytes().to_vec()));
+        assert_eq!(decode("data:,"), ("".to_owned(), vec![]));
+
+        assert_eq!(decode_data_uri("http://example.com/a.png"), Err(DecodeError::InvalidDataUri));
+        assert_eq!(decode_data_uri("data:image/png;base64"), Err(DecodeError::InvalidDataUri));
+        assert_eq!(decode_data_uri("data:,%4"), Err(DecodeError::InvalidDataUri));
+        assert_eq!(decode_data_uri("data:;base64,Zm9vY"), Err(DecodeError::InvalidBase64));
+    }
+
+    #[test]
+    fn test_decode_quoted_printable() {
+        assert_eq!(decode_quoted_printable("").unwrap(), b"");
+        assert_eq!(decode_quoted_printable("K=C3=B6ln").unwrap(), "Köln".as_bytes());
+        assert_eq!(decode_quoted_printable("K=f6ln").unwrap(), b"K\xf6ln");
+        assert_eq!(decode_quoted_printable("a=3Db=\r\nc=\nd").unwrap(), b"a=bcd");
+
+        assert_eq!(decode_quoted_printable("a="), Err(DecodeError::InvalidQuotedPrintable));
+        assert_eq!(decode_quoted_printable("a=4"), Err(DecodeError::InvalidQuotedPrintable));
+        assert_eq!(decode_quoted_printable("a=XYb"), Err(DecodeError::InvalidQuotedPrintable));
+    }
+
+    #[test]
+    fn test_charsets() {
+        assert_eq!(Charset::from_name("utf-8"), Ok(Charset::Utf8));
+        assert_eq!(Charset::from_name("Iso-8859-1"), Ok(Charset::Latin1));
+        assert_eq!(Charset::from_name("windows-1252"), Ok(Charset::Windows1252));
+        assert_eq!(Charset::from_name("KOI8-R"), Err(DecodeError::UnknownCharset("KOI8-R".to_owned())));
+
+        assert_eq!(Charset::Utf8.decode("Köln".as_bytes().to_vec()).unwrap(), "Köln");
+        assert_eq!(Charset::Utf8.decode(vec![b'K', 0xf6]), Err(DecodeError::InvalidUtf8));
+        assert_eq!(Charset::Latin1.decode(vec![b'K', 0xf6, 0x80]).unwrap(), "Kö\u{80}");
+        assert_eq!(Charset::Windows1252.decode(vec![b'K', 0xf6, 0x80, 0x93, 0x81]).unwrap(), "Kö€\u{201C}\u{81}");
+    }
+}
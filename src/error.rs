@@ -1,13 +1,39 @@
+use std::io;
+
 use thiserror::Error;
 
-use ::parser::ParseErrorReason;
+pub use ::parser::{ParseError, ParseErrorKind};
 
-#[derive(Debug, Clone, Error)]
+#[derive(Debug, Error)]
 pub enum VObjectError {
     #[error("failed to parse: {}", source)]
     Parse {
         #[from]
-        source: ParseErrorReason,
+        source: ParseError,
+    },
+
+    #[error("failed to read input: {}", source)]
+    Io {
+        #[from]
+        source: io::Error,
+    },
+
+    #[error("failed to decode value: {}", source)]
+    Decode {
+        #[from]
+        source: DecodeError,
+    },
+
+    #[error("invalid {} value: {}", expected, value)]
+    InvalidValue {
+        expected: &'static str,
+        value: String,
+    },
+
+    #[error("{} has no {} property", component, property)]
+    MissingProperty {
+        component: String,
+        property: &'static str,
     },
 
     #[error("Not a Vcard")]
@@ -24,4 +50,48 @@ pub enum VObjectError {
     },
 }
 
+/// A failure to decode a property value, see `Property::decode_value`.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DecodeError {
+    #[error("invalid base64 data")]
+    InvalidBase64,
+
+    #[error("invalid quoted-printable data")]
+    InvalidQuotedPrintable,
+
+    #[error("unknown charset: {}", _0)]
+    UnknownCharset(String),
+
+    #[error("value is not valid UTF-8")]
+    InvalidUtf8,
+
+    #[error("invalid data URI")]
+    InvalidDataUri,
+}
+
+/// A violation of the property rules of a well-known component type, see
+/// `Component::validate`.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ValidationError {
+    #[error("{} is missing the required property {}", component, property)]
+    MissingProperty {
+        component: String,
+        property: &'static str,
+    },
+
+    #[error("{} has {} {} properties, but only one is allowed", component, count, property)]
+    TooManyProperties {
+        component: String,
+        property: &'static str,
+        count: usize,
+    },
+
+    #[error("{} must not have both {} and {}", component, first, second)]
+    ConflictingProperties {
+        component: String,
+        first: &'static str,
+        second: &'static str,
+    },
+}
+
 pub(crate) type VObjectResult<T> = Result<T, VObjectError>;
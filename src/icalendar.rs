@@ -1,8 +1,11 @@
 use std::collections::BTreeMap;
+use std::ops::Deref;
 
 use component::Component;
 use component::parse_component;
+use param::Parameters;
 use property::Property;
+use timezone;
 use error::*;
 
 #[cfg(feature = "timeconversions")] use chrono::NaiveDateTime;
@@ -10,6 +13,7 @@ use error::*;
 
 #[cfg(feature = "timeconversions")] use util::DATE_TIME_FMT;
 #[cfg(feature = "timeconversions")] use util::DATE_FMT;
+#[cfg(feature = "timeconversions")] use value::{invalid, DateTimeValue, Duration};
 
 /// An ICalendar representing type
 #[derive(Debug)]
@@ -34,12 +38,12 @@ impl ICalendar {
 
     /// Add an event to the calendar
     pub fn add_event(&mut self, builder: EventBuilder) {
-        self.0.subcomponents.push(builder.into_component())
+        self.0.add_subcomponent(builder.into_component())
     }
 
     /// Chainable variant of `ICalendar::add_event()`.
     pub fn with_event(mut self, builder: EventBuilder) -> Self {
-        self.0.subcomponents.push(builder.into_component());
+        self.0.add_subcomponent(builder.into_component());
         self
     }
 
@@ -63,15 +67,10 @@ impl ICalendar {
     /// For getting a Event-instance iterator from this, one can use this as follows:
     ///
     /// ```
-    /// # use std::collections::BTreeMap;
     /// # use vobject::component::Component;
     /// # use vobject::icalendar::Event;
     /// # use vobject::icalendar::ICalendar;
-    /// # let icalendar = ICalendar::from_component(Component {
-    /// #     name:          "VCALENDAR".to_owned(),
-    /// #     props:         BTreeMap::new(),
-    /// #     subcomponents: vec![]
-    /// # }).unwrap();
+    /// # let icalendar = ICalendar::from_component(Component::new("VCALENDAR")).unwrap();
     /// icalendar
     ///     .events()
     ///     .filter_map(Result::ok)
@@ -83,13 +82,241 @@ impl ICalendar {
         EventIterator::new(self.0.subcomponents.iter())
     }
 
+    /// Get an iterator over the events of this calendar together with the calendar-level
+    /// properties and time zones needed to interpret them, see `EventContext`.
+    pub fn events_with_context(&self) -> impl Iterator<Item = EventContext<'_>> {
+        self.0.subcomponents
+            .iter()
+            .filter(|c| c.name == "VEVENT")
+            .map(move |event| {
+                let mut tzids = event.props.values()
+                    .flatten()
+                    .filter_map(|p| p.get_param("TZID"))
+                    .collect::<Vec<_>>();
+                tzids.sort_unstable();
+                tzids.dedup();
+
+                EventContext {
+                    event,
+                    prodid: self.0.get_only("PRODID"),
+                    calscale: self.0.get_only("CALSCALE"),
+                    method: self.0.get_only("METHOD"),
+                    timezones: tzids.into_iter().filter_map(|tzid| self.timezone(tzid)).collect(),
+                }
+            })
+    }
+
+    /// Get the events of this calendar sorted by their `DTSTART`, see
+    /// `Property::value_as_datetime`. Events without a readable `DTSTART` come last, and events
+    /// with the same start keep their order.
+    ///
+    /// UTC times are compared as such, and local times with a `TZID` are converted to UTC using
+    /// the calendar's `VTIMEZONE`s, see `ICalendar::utc_offset`. Dates count as midnight, and
+    /// floating times and local times in unknown time zones are compared as if they were UTC.
+    #[cfg(feature = "timeconversions")]
+    pub fn events_sorted_by_start(&self) -> Vec<&Component> {
+        let start = |event: &Component| {
+            let prop = event.get_only("DTSTART")?;
+            Some(match prop.value_as_datetime().ok()? {
+                DateTimeValue::Date(date) => date.and_hms_opt(0, 0, 0)?,
+                DateTimeValue::Utc(datetime) => datetime.naive_utc(),
+                DateTimeValue::Floating(datetime) => datetime,
+                DateTimeValue::Local { datetime, .. } => {
+                    let offset = self.utc_offset(prop).unwrap_or(0);
+                    datetime - chrono::Duration::seconds(offset.into())
+                },
+            })
+        };
+
+        let mut events = self.0.subcomponents
+            .iter()
+            .filter(|c| c.name == "VEVENT")
+            .map(|c| (start(c), c))
+            .collect::<Vec<_>>();
+        events.sort_by_key(|&(start, _)| (start.is_none(), start));
+        events.into_iter().map(|(_, c)| c).collect()
+    }
+
+    /// Get an iterator over the to-dos (`VTODO` components) in this calendar.
+    pub fn todos(&self) -> impl Iterator<Item = &Component> {
+        self.0.subcomponents.iter().filter(|c| c.name == "VTODO")
+    }
+
+    /// Get an iterator over the journal entries (`VJOURNAL` components) in this calendar.
+    pub fn journals(&self) -> impl Iterator<Item = &Component> {
+        self.0.subcomponents.iter().filter(|c| c.name == "VJOURNAL")
+    }
+
+    /// Get the `VTIMEZONE` with the given `TZID`.
+    pub fn timezone(&self, tzid: &str) -> Option<&Component> {
+        self.0.subcomponents
+            .iter()
+            .filter(|c| c.name == "VTIMEZONE")
+            .find(|c| c.get_only("TZID").map(|p| p.value_as_string()).as_deref() == Some(tzid))
+    }
+
+    /// The UTC offset in seconds of a local time qualified by a `TZID` parameter, such as
+    /// `DTSTART;TZID=Europe/Vienna:20240115T133000`, using the matching `VTIMEZONE` of this
+    /// calendar.
+    ///
+    /// Returns `None` if the property has no `TZID`, the calendar has no such `VTIMEZONE`, or
+    /// the offset cannot be determined. See `vobject::timezone` for the limitations.
+    pub fn utc_offset(&self, prop: &Property) -> Option<i32> {
+        let tz = self.timezone(prop.get_param("TZID")?)?;
+        timezone::utc_offset_at(tz, &prop.raw_value)
+    }
+
+    /// The iTIP method of this calendar, given by its `METHOD` property, or `None` if it has
+    /// none and is therefore not an iTIP message.
+    ///
+    /// This only surfaces the method; how `ATTENDEE` and `ORGANIZER` are to be interpreted,
+    /// and what to do with the message, is up to the caller.
+    pub fn method(&self) -> Option<Method> {
+        self.0.get_only("METHOD").map(|p| Method::from_name(p.value_as_string().trim()))
+    }
+
+    /// For a `METHOD:REPLY`, the `ATTENDEE`s of the calendar's components with their
+    /// `PARTSTAT`, in order. Returns nothing for other methods.
+    ///
+    /// Only the direct subcomponents, such as `VEVENT` or `VTODO`, are searched; attendees of
+    /// a `VALARM` are not responses.
+    pub fn attendee_responses(&self) -> Vec<AttendeeResponse<'_>> {
+        if self.method() != Some(Method::Reply) {
+            return vec![];
+        }
+
+        self.0.subcomponents
+            .iter()
+            .flat_map(|component| {
+                component.get_all("ATTENDEE").iter().map(move |attendee| AttendeeResponse {
+                    component,
+                    attendee,
+                    partstat: attendee.get_param("PARTSTAT"),
+                })
+            })
+            .collect()
+    }
+
+    /// Unwrap the underlying `VCALENDAR` component.
+    pub fn into_component(self) -> Component {
+        self.0
+    }
+
     make_getter_function_for_optional!(version, "VERSION", Version);
     make_getter_function_for_optional!(prodid, "PRODID", Prodid);
 }
 
+impl Deref for ICalendar {
+    type Target = Component;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
 create_data_type!(Version);
 create_data_type!(Prodid);
 
+/// The iTIP method of a calendar, see RFC 5546 and `ICalendar::method`.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum Method {
+    Publish,
+    Request,
+    Reply,
+    Add,
+    Cancel,
+    Refresh,
+    Counter,
+    DeclineCounter,
+    /// A method this crate does not know, such as an `X-` method, in uppercase.
+    Other(String),
+}
+
+impl Method {
+    const ALL: [(Method, &'static str); 8] = [
+        (Method::Publish, "PUBLISH"),
+        (Method::Request, "REQUEST"),
+        (Method::Reply, "REPLY"),
+        (Method::Add, "ADD"),
+        (Method::Cancel, "CANCEL"),
+        (Method::Refresh, "REFRESH"),
+        (Method::Counter, "COUNTER"),
+        (Method::DeclineCounter, "DECLINECOUNTER"),
+    ];
+
+    /// Look up a method by its name, ignoring case.
+    pub fn from_name(name: &str) -> Method {
+        Method::ALL.iter()
+            .find(|(_, n)| n.eq_ignore_ascii_case(name))
+            .map(|(m, _)| m.clone())
+            .unwrap_or_else(|| Method::Other(name.to_ascii_uppercase()))
+    }
+
+    /// The name of the method in a `METHOD` property, such as `REQUEST`.
+    pub fn as_str(&self) -> &str {
+        match *self {
+            Method::Other(ref name) => name,
+            ref m => Method::ALL.iter().find(|(n, _)| n == m).unwrap().1,
+        }
+    }
+}
+
+/// An attendee's answer in an iTIP reply, see `ICalendar::attendee_responses`.
+#[derive(Debug, Clone)]
+pub struct AttendeeResponse<'a> {
+    /// The component the attendee belongs to, such as a `VEVENT`.
+    pub component: &'a Component,
+    /// The `ATTENDEE` property, whose value is the attendee's address.
+    pub attendee: &'a Property,
+    /// The `PARTSTAT` parameter, such as `ACCEPTED` or `DECLINED`. If it is missing, RFC 5545
+    /// defines it as `NEEDS-ACTION`.
+    pub partstat: Option<&'a str>,
+}
+
+/// An `ATTENDEE` or `ORGANIZER` with the parameters a calendar client usually needs, see
+/// `Component::attendees`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attendee {
+    /// The address, with a `mailto:` scheme removed. Other URIs are kept as they are.
+    pub address: String,
+    /// The `CN` parameter, the display name.
+    pub cn: Option<String>,
+    /// The `ROLE` parameter, such as `REQ-PARTICIPANT` or `CHAIR`.
+    pub role: Option<String>,
+    /// The `PARTSTAT` parameter, such as `ACCEPTED` or `NEEDS-ACTION`.
+    pub partstat: Option<String>,
+    /// The `RSVP` parameter. `None` if it is missing or neither `TRUE` nor `FALSE`.
+    pub rsvp: Option<bool>,
+}
+
+impl Attendee {
+    /// Decode an `ATTENDEE` or `ORGANIZER` property. Missing parameters are left as `None`.
+    pub fn from_property(prop: &Property) -> Attendee {
+        let value = prop.raw_value.trim();
+        let address = match value.get(..7) {
+            Some(scheme) if scheme.eq_ignore_ascii_case("mailto:") => &value[7..],
+            _ => value,
+        };
+        let param = |name: &str| prop.get_param(name).map(str::to_owned);
+
+        Attendee {
+            address: address.to_owned(),
+            cn: param("CN"),
+            role: param("ROLE"),
+            partstat: param("PARTSTAT"),
+            rsvp: prop.get_param("RSVP").and_then(|rsvp| {
+                if rsvp.eq_ignore_ascii_case("TRUE") {
+                    Some(true)
+                } else if rsvp.eq_ignore_ascii_case("FALSE") {
+                    Some(false)
+                } else {
+                    None
+                }
+            }),
+        }
+    }
+}
+
 pub struct EventIterator<'a>(::std::slice::Iter<'a, Component>);
 
 impl<'a> EventIterator<'a> {
@@ -107,6 +334,23 @@ impl<'a> Iterator for EventIterator<'a> {
 
 }
 
+/// An event with the parts of its calendar that apply to it, see
+/// `ICalendar::events_with_context`.
+#[derive(Debug, Clone)]
+pub struct EventContext<'a> {
+    /// The `VEVENT` component.
+    pub event: &'a Component,
+    /// The `PRODID` of the calendar.
+    pub prodid: Option<&'a Property>,
+    /// The `CALSCALE` of the calendar. If there is none, the calendar is Gregorian.
+    pub calscale: Option<&'a Property>,
+    /// The `METHOD` of the calendar, for iTIP messages.
+    pub method: Option<&'a Property>,
+    /// The `VTIMEZONE`s named by a `TZID` parameter of the event's properties, once each.
+    /// Time zones the calendar does not define are left out.
+    pub timezones: Vec<&'a Component>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Event<'a>(&'a Component);
 
@@ -202,17 +446,62 @@ impl AsDateTime for Dtstamp {
 
 }
 
+/// The absolute time at which a `VALARM` triggers, given the event or to-do it belongs to.
+///
+/// A `TRIGGER;VALUE=DATE-TIME` is returned as it is. A duration such as `-PT15M` is added to
+/// the `DTSTART` of `parent`, or to its end with `RELATED=END`. The end is `DTEND`, or `DUE`
+/// for a `VTODO`, or else `DTSTART` plus `DURATION`. Fails with
+/// `VObjectError::MissingProperty` if the alarm has no `TRIGGER` or the parent lacks the time
+/// it is related to.
+#[cfg(feature = "timeconversions")]
+pub fn trigger_time(alarm: &Component, parent: &Component) -> VObjectResult<DateTimeValue> {
+    let missing = |c: &Component, property| VObjectError::MissingProperty {
+        component: c.name.clone(),
+        property,
+    };
+
+    let trigger = alarm.get_only("TRIGGER").ok_or_else(|| missing(alarm, "TRIGGER"))?;
+    if trigger.get_param("VALUE").is_some_and(|v| v.eq_ignore_ascii_case("DATE-TIME")) {
+        return DateTimeValue::from_property(trigger);
+    }
+    let offset = Duration::from_property(trigger)?;
+
+    let start = || {
+        let dtstart = parent.get_only("DTSTART").ok_or_else(|| missing(parent, "DTSTART"))?;
+        DateTimeValue::from_property(dtstart)
+    };
+    let end_name = if parent.name == "VTODO" { "DUE" } else { "DTEND" };
+    let anchor = if trigger.get_param("RELATED").is_some_and(|r| r.eq_ignore_ascii_case("END")) {
+        match (parent.get_only(end_name), parent.get_only("DURATION")) {
+            (Some(end), _) => DateTimeValue::from_property(end)?,
+            (None, Some(duration)) => {
+                let duration = Duration::from_property(duration)?;
+                start()?.add_duration(&duration)
+                    .ok_or_else(|| invalid("DURATION", &duration.to_string()))?
+            },
+            (None, None) => return Err(missing(parent, end_name)),
+        }
+    } else {
+        start()?
+    };
+
+    anchor.add_duration(&offset).ok_or_else(|| invalid("TRIGGER", &trigger.raw_value))
+}
+
 #[derive(Clone, Debug)]
 pub struct EventBuilder(Component);
 
 macro_rules! make_setter_function_for {
-    ($fnname:ident, $name:expr, $type:ty, $tostring:expr) => {
-        pub fn $fnname(&mut self, value: $type, params: Option<BTreeMap<String, String>>) {
+    ($(#[$attr:meta])* $fnname:ident, $name:expr, $type:ty, $tostring:expr) => {
+        $(#[$attr])*
+        pub fn $fnname(&mut self, value: $type, params: Option<Parameters>) {
             let property = Property {
                 name:       String::from($name),
-                params:     params.unwrap_or_else(|| BTreeMap::new()),
+                params:     params.unwrap_or_default(),
                 raw_value:  $tostring(value),
                 prop_group: None,
+                param_breaks: BTreeMap::new(),
+                source_span: None,
             };
 
             self.0.set(property);
@@ -221,13 +510,16 @@ macro_rules! make_setter_function_for {
 }
 
 macro_rules! make_function_for {
-    ($fnname:ident, $name:expr, $type:ty, $tostring:expr) => {
-        pub fn $fnname(mut self, value: $type, params: Option<BTreeMap<String, String>>) -> Self {
+    ($(#[$attr:meta])* $fnname:ident, $name:expr, $type:ty, $tostring:expr) => {
+        $(#[$attr])*
+        pub fn $fnname(mut self, value: $type, params: Option<Parameters>) -> Self {
             let property = Property {
                 name:       String::from($name),
-                params:     params.unwrap_or_else(|| BTreeMap::new()),
+                params:     params.unwrap_or_default(),
                 raw_value:  $tostring(value),
                 prop_group: None,
+                param_breaks: BTreeMap::new(),
+                source_span: None,
             };
 
             self.0.push(property);
@@ -243,177 +535,201 @@ impl EventBuilder {
         self.0
     }
 
-    /// Setter for "DTEND" property
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is overridden. Old values are dropped silently:
-    make_setter_function_for!(set_dtend, "DTEND", Dtend, Dtend::into_raw);
-
-    /// Setter for "DTSTART" property
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is overridden. Old values are dropped silently:
-    make_setter_function_for!(set_dtstart, "DTSTART", Dtstart, Dtstart::into_raw);
-
-    /// Setter for "DTSTAMP" property
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is overridden. Old values are dropped silently:
-    make_setter_function_for!(set_dtstamp, "DTSTAMP", Dtstamp, Dtstamp::into_raw);
-
-    /// Setter for "UID" property
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is overridden. Old values are dropped silently:
-    make_setter_function_for!(set_uid, "UID", Uid, Uid::into_raw);
-
-    /// Setter for "DESCRIPTION" property
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is overridden. Old values are dropped silently:
-    make_setter_function_for!(set_description, "DESCRIPTION", Description, Description::into_raw);
-
-    /// Setter for "SUMMARY" property
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is overridden. Old values are dropped silently:
-    make_setter_function_for!(set_summary, "SUMMARY", Summary, Summary::into_raw);
-
-    /// Setter for "URL" property
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is overridden. Old values are dropped silently:
-    make_setter_function_for!(set_url, "URL", Url, Url::into_raw);
-
-    /// Setter for "LOCATION" property
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is overridden. Old values are dropped silently:
-    make_setter_function_for!(set_location, "LOCATION", Location, Location::into_raw);
-
-    /// Setter for "CLASS" property
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is overridden. Old values are dropped silently:
-    make_setter_function_for!(set_class, "CLASS", Class, Class::into_raw);
-
-    /// Setter for "CATEGORIES" property
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is overridden. Old values are dropped silently:
-    make_setter_function_for!(set_categories, "CATEGORIES", Categories, Categories::into_raw);
-
-    /// Setter for "TRANSP" property
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is overridden. Old values are dropped silently:
-    make_setter_function_for!(set_transp, "TRANSP", Transp, Transp::into_raw);
-
-    /// Setter for "RRULE" property
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is overridden. Old values are dropped silently:
-    make_setter_function_for!(set_rrule, "RRULE", Rrule, Rrule::into_raw);
+    make_setter_function_for!(
+        /// Setter for "DTEND" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_dtend, "DTEND", Dtend, Dtend::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "DTSTART" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_dtstart, "DTSTART", Dtstart, Dtstart::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "DTSTAMP" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_dtstamp, "DTSTAMP", Dtstamp, Dtstamp::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "UID" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_uid, "UID", Uid, Uid::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "DESCRIPTION" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_description, "DESCRIPTION", Description, Description::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "SUMMARY" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_summary, "SUMMARY", Summary, Summary::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "URL" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_url, "URL", Url, Url::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "LOCATION" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_location, "LOCATION", Location, Location::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "CLASS" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_class, "CLASS", Class, Class::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "CATEGORIES" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_categories, "CATEGORIES", Categories, Categories::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "TRANSP" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_transp, "TRANSP", Transp, Transp::into_raw);
+
+    make_setter_function_for!(
+        /// Setter for "RRULE" property
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is overridden. Old values are dropped silently:
+        set_rrule, "RRULE", Rrule, Rrule::into_raw);
 
     //
     // chainable builders
     //
 
-    /// Chainable setter for "DTEND" property.
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is added, not overridden.
-    make_function_for!(with_dtend, "DTEND", Dtend, Dtend::into_raw);
-
-    /// Chainable setter for "DTSTART" property.
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is added, not overridden.
-    make_function_for!(with_dtstart, "DTSTART", Dtstart, Dtstart::into_raw);
-
-    /// Chainable setter for "DTSTAMP" property.
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is added, not overridden.
-    make_function_for!(with_dtstamp, "DTSTAMP", Dtstamp, Dtstamp::into_raw);
-
-    /// Chainable setter for "UID" property.
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is added, not overridden.
-    make_function_for!(with_uid, "UID", Uid, Uid::into_raw);
-
-    /// Chainable setter for "DESCRIPTION" property.
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is added, not overridden.
-    make_function_for!(with_description, "DESCRIPTION", Description, Description::into_raw);
-
-    /// Chainable setter for "SUMMARY" property.
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is added, not overridden.
-    make_function_for!(with_summary, "SUMMARY", Summary, Summary::into_raw);
-
-    /// Chainable setter for "URL" property.
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is added, not overridden.
-    make_function_for!(with_url, "URL", Url, Url::into_raw);
-
-    /// Chainable setter for "LOCATION" property.
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is added, not overridden.
-    make_function_for!(with_location, "LOCATION", Location, Location::into_raw);
-
-    /// Chainable setter for "CLASS" property.
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is added, not overridden.
-    make_function_for!(with_class, "CLASS", Class, Class::into_raw);
-
-    /// Chainable setter for "CATEGORIES" property.
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is added, not overridden.
-    make_function_for!(with_categories, "CATEGORIES", Categories, Categories::into_raw);
-
-    /// Chainable setter for "TRANSP" property.
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is added, not overridden.
-    make_function_for!(with_transp, "TRANSP", Transp, Transp::into_raw);
-
-    /// Chainable setter for "RRULE" property.
-    ///
-    /// # Notice
-    ///
-    /// Internally, the property is added, not overridden.
-    make_function_for!(with_rrule, "RRULE", Rrule, Rrule::into_raw);
+    make_function_for!(
+        /// Chainable setter for "DTEND" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_dtend, "DTEND", Dtend, Dtend::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "DTSTART" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_dtstart, "DTSTART", Dtstart, Dtstart::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "DTSTAMP" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_dtstamp, "DTSTAMP", Dtstamp, Dtstamp::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "UID" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_uid, "UID", Uid, Uid::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "DESCRIPTION" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_description, "DESCRIPTION", Description, Description::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "SUMMARY" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_summary, "SUMMARY", Summary, Summary::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "URL" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_url, "URL", Url, Url::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "LOCATION" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_location, "LOCATION", Location, Location::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "CLASS" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_class, "CLASS", Class, Class::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "CATEGORIES" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_categories, "CATEGORIES", Categories, Categories::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "TRANSP" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_transp, "TRANSP", Transp, Transp::into_raw);
+
+    make_function_for!(
+        /// Chainable setter for "RRULE" property.
+        ///
+        /// # Notice
+        ///
+        /// Internally, the property is added, not overridden.
+        with_rrule, "RRULE", Rrule, Rrule::into_raw);
 
 }
 
@@ -426,7 +742,7 @@ mod tests {
 
     use super::*;
 
-    const TEST_ENTRY : &'static str =
+    const TEST_ENTRY : &str =
             "BEGIN:VCALENDAR\n\
             VERSION:2.0\n\
             PRODID:http://www.example.com/calendarapplication/\n\
@@ -444,7 +760,7 @@ mod tests {
             END:VEVENT\n\
             END:VCALENDAR\n";
 
-    const TEST_ENTRY_OC : &'static str = // Lets see how owncloud foo works here
+    const TEST_ENTRY_OC : &str = // Lets see how owncloud foo works here
         "BEGIN:VCALENDAR\n\
         VERSION:2.0\n\
         PRODID:ownCloud Calendar\n\
@@ -566,4 +882,70 @@ mod tests {
 
     }
 
+    #[test]
+    fn test_events_sorted_by_start() {
+        let cal = ICalendar::build("BEGIN:VCALENDAR\n\
+            VERSION:2.0\n\
+            PRODID:test\n\
+            BEGIN:VTIMEZONE\n\
+            TZID:Europe/Vienna\n\
+            BEGIN:STANDARD\n\
+            DTSTART:19701025T030000\n\
+            TZOFFSETFROM:+0200\n\
+            TZOFFSETTO:+0100\n\
+            END:STANDARD\n\
+            END:VTIMEZONE\n\
+            BEGIN:VEVENT\nUID:no-start\nEND:VEVENT\n\
+            BEGIN:VEVENT\nUID:utc-10\nDTSTART:20240115T100000Z\nEND:VEVENT\n\
+            BEGIN:VEVENT\nUID:vienna-10\nDTSTART;TZID=Europe/Vienna:20240115T100000\nEND:VEVENT\n\
+            BEGIN:VEVENT\nUID:invalid\nDTSTART:2024-01-15\nEND:VEVENT\n\
+            BEGIN:VEVENT\nUID:date\nDTSTART;VALUE=DATE:20240115\nEND:VEVENT\n\
+            BEGIN:VTODO\nUID:todo\nDTSTART:20240101T000000Z\nEND:VTODO\n\
+            BEGIN:VEVENT\nUID:floating-930\nDTSTART:20240115T093000\nEND:VEVENT\n\
+            END:VCALENDAR\n").unwrap();
+
+        let uids = cal.events_sorted_by_start()
+            .iter()
+            .map(|e| e.get_only("UID").unwrap().raw_value.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(uids, ["date", "vienna-10", "floating-930", "utc-10", "no-start", "invalid"]);
+    }
+
+    #[test]
+    fn test_trigger_time() {
+        use chrono::{TimeZone, Utc};
+        use component::parse_component;
+        use value::DateTimeValue;
+
+        let event = parse_component("BEGIN:VEVENT\n\
+                                     UID:1\n\
+                                     DTSTART:20240115T130000Z\n\
+                                     DURATION:PT1H\n\
+                                     BEGIN:VALARM\n\
+                                     TRIGGER:-PT15M\n\
+                                     END:VALARM\n\
+                                     BEGIN:VALARM\n\
+                                     TRIGGER;RELATED=END:PT5M\n\
+                                     END:VALARM\n\
+                                     BEGIN:VALARM\n\
+                                     TRIGGER;VALUE=DATE-TIME:20240114T090000Z\n\
+                                     END:VALARM\n\
+                                     END:VEVENT\n").unwrap();
+        let utc = |h, m| DateTimeValue::Utc(Utc.with_ymd_and_hms(2024, 1, 15, h, m, 0).unwrap());
+
+        assert_eq!(trigger_time(&event.subcomponents[0], &event).unwrap(), utc(12, 45));
+        assert_eq!(trigger_time(&event.subcomponents[1], &event).unwrap(), utc(14, 5));
+        assert_eq!(trigger_time(&event.subcomponents[2], &event).unwrap(),
+                   DateTimeValue::Utc(Utc.with_ymd_and_hms(2024, 1, 14, 9, 0, 0).unwrap()));
+
+        let todo = parse_component("BEGIN:VTODO\nUID:2\nDTSTART:20240115T130000Z\nEND:VTODO\n").unwrap();
+        match trigger_time(&event.subcomponents[1], &todo) {
+            Err(VObjectError::MissingProperty { ref component, property }) => {
+                assert_eq!(component, "VTODO");
+                assert_eq!(property, "DUE");
+            },
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(trigger_time(&Component::new("VALARM"), &event).is_err());
+    }
 }
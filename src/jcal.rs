@@ -0,0 +1,529 @@
+//! Conversion of components to jCal (RFC 7265) and jCard (RFC 7095), the JSON formats for
+//! iCalendar and vCard.
+
+use serde_json::{Map, Number, Value};
+
+use component::Component;
+use error::*;
+use property::{escape_chars, split_unescaped, unescape_chars, Property};
+use value::{invalid, ValueType};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Jcal,
+    Jcard,
+}
+
+/// Properties whose value consists of `;`-separated components.
+const JCAL_STRUCTURED: &[&str] = &["GEO", "REQUEST-STATUS"];
+const JCARD_STRUCTURED: &[&str] = &["ADR", "CLIENTPIDMAP", "GENDER", "N", "ORG"];
+
+/// Text properties whose value is a `,`-separated list.
+const JCAL_MULTI_VALUED: &[&str] = &["CATEGORIES", "RESOURCES"];
+const JCARD_MULTI_VALUED: &[&str] = &["CATEGORIES", "NICKNAME"];
+
+/// Value types that may be given as a `,`-separated list, as in `EXDATE` or `FREEBUSY`.
+const LIST_TYPES: &[&str] = &["date", "date-time", "period"];
+
+impl Format {
+    /// The lowercase default type of a property, or `"unknown"` for properties this crate does
+    /// not know.
+    fn default_type(self, name: &str) -> String {
+        let value_type = match self {
+            Format::Jcal => ValueType::default_for_icalendar(name),
+            Format::Jcard => ValueType::default_for_vcard(name),
+        };
+        value_type
+            .map(|t| t.as_str().to_ascii_lowercase())
+            .unwrap_or_else(|| "unknown".to_owned())
+    }
+
+    fn is_structured(self, name: &str) -> bool {
+        let names = match self {
+            Format::Jcal => JCAL_STRUCTURED,
+            Format::Jcard => JCARD_STRUCTURED,
+        };
+        names.iter().any(|n| n.eq_ignore_ascii_case(name))
+    }
+
+    fn is_multi_valued(self, name: &str) -> bool {
+        let names = match self {
+            Format::Jcal => JCAL_MULTI_VALUED,
+            Format::Jcard => JCARD_MULTI_VALUED,
+        };
+        names.iter().any(|n| n.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Convert a component, usually a `VCALENDAR`, to jCal.
+///
+/// Each component becomes a `[name, properties, subcomponents]` array and each property a
+/// `[name, parameters, type, value...]` array, with names and parameter keys lowercased and a
+/// property group given as the `group` parameter. The type is taken from the `VALUE`
+/// parameter, or is the default type of the property, or `"unknown"` for properties this crate
+/// does not know. Dates, times and UTC offsets are converted to the extended format used by
+/// jCal, and numbers, booleans and recurrence rules to their JSON counterparts.
+pub fn to_jcal(c: &Component) -> Value {
+    component_to_json(c, Format::Jcal)
+}
+
+/// Convert a `VCARD` to jCard.
+///
+/// This works like `to_jcal()`, using the property types of vCard 4.0. A jCard has no
+/// subcomponents, so it is a `["vcard", properties]` array, with `VERSION` first.
+pub fn to_jcard(c: &Component) -> Value {
+    component_to_json(c, Format::Jcard)
+}
+
+fn component_to_json(c: &Component, format: Format) -> Value {
+    let mut props = vec![];
+    if format == Format::Jcard {
+        props.extend(c.get_all("VERSION").iter().map(|p| property_to_json(p, format)));
+    }
+    for (key, ps) in &c.props {
+        if format == Format::Jcard && key == "VERSION" {
+            continue;
+        }
+        props.extend(ps.iter().map(|p| property_to_json(p, format)));
+    }
+
+    let mut rv = vec![Value::String(c.name.to_lowercase()), Value::Array(props)];
+    if format == Format::Jcal {
+        let subs = c.subcomponents.iter().map(|s| component_to_json(s, format)).collect();
+        rv.push(Value::Array(subs));
+    }
+    Value::Array(rv)
+}
+
+fn property_to_json(p: &Property, format: Format) -> Value {
+    let mut params = Map::new();
+    if let Some(ref group) = p.prop_group {
+        params.insert("group".to_owned(), Value::String(group.clone()));
+    }
+    for (key, values) in &p.params {
+        if key.eq_ignore_ascii_case("VALUE") {
+            continue;
+        }
+        let value = match values[..] {
+            [ref v] => Value::String(v.clone()),
+            _ => Value::Array(values.iter().cloned().map(Value::String).collect()),
+        };
+        params.insert(key.to_lowercase(), value);
+    }
+
+    let value_type = p.get_param("VALUE")
+        .map(str::to_lowercase)
+        .unwrap_or_else(|| format.default_type(&p.name));
+
+    let mut rv = vec![
+        Value::String(p.name.to_lowercase()),
+        Value::Object(params),
+        Value::String(value_type.clone()),
+    ];
+
+    let components = split_unescaped(&p.raw_value, ';');
+    if format.is_structured(&p.name) && components.len() > 1 {
+        let components = components.into_iter().map(|c| {
+            let items = split_unescaped(c, ',');
+            if items.len() > 1 {
+                Value::Array(items.into_iter().map(|i| typed_value(&value_type, i)).collect())
+            } else {
+                typed_value(&value_type, c)
+            }
+        });
+        rv.push(Value::Array(components.collect()));
+    } else if value_type == "recur" {
+        rv.push(recur_to_json(&p.raw_value));
+    } else if format.is_multi_valued(&p.name) || LIST_TYPES.contains(&&value_type[..]) {
+        rv.extend(split_unescaped(&p.raw_value, ',').into_iter().map(|v| typed_value(&value_type, v)));
+    } else {
+        rv.push(typed_value(&value_type, &p.raw_value));
+    }
+
+    Value::Array(rv)
+}
+
+/// A function converting values to their jCal form, and the separators it inserts.
+type Formatter = (fn(&str) -> String, &'static [char]);
+
+/// The formatter for values of a date, time or UTC offset type.
+fn formatter(value_type: &str) -> Option<Formatter> {
+    let rv: Formatter = match value_type {
+        "date" => (format_date, &['-']),
+        "date-time" | "timestamp" => (format_date_time, &['-', ':']),
+        "time" => (format_time, &[':']),
+        "date-and-or-time" => (format_date_and_or_time, &['-', ':']),
+        "utc-offset" => (format_utc_offset, &[':']),
+        "period" => (format_period, &['-', ':']),
+        _ => return None,
+    };
+    Some(rv)
+}
+
+/// Convert a single escaped value of the given type. Only text is unescaped, values of other
+/// types, such as URIs, are kept as they are.
+fn typed_value(value_type: &str, raw: &str) -> Value {
+    let s = if value_type == "text" { unescape_chars(raw) } else { raw.to_owned() };
+    match value_type {
+        "integer" => s.parse::<i64>().map(Value::from).unwrap_or(Value::String(s)),
+        "float" => {
+            s.parse::<f64>()
+                .ok()
+                .and_then(Number::from_f64)
+                .map(Value::Number)
+                .unwrap_or(Value::String(s))
+        },
+        "boolean" if s.eq_ignore_ascii_case("TRUE") => Value::Bool(true),
+        "boolean" if s.eq_ignore_ascii_case("FALSE") => Value::Bool(false),
+        _ => match formatter(value_type) {
+            Some((format, _)) => Value::String(format(&s)),
+            None => Value::String(s),
+        },
+    }
+}
+
+/// Convert an `RRULE` value to an object keyed by the lowercased rule parts. Parts with
+/// several values become arrays.
+fn recur_to_json(raw: &str) -> Value {
+    let mut rv = Map::new();
+    for part in raw.split(';') {
+        let (key, value) = match part.find('=') {
+            Some(i) => (part[..i].to_lowercase(), &part[i + 1..]),
+            None => continue,
+        };
+        let values = value.split(',').map(|v| match &key[..] {
+            "until" => Value::String(format_date_and_or_time(v)),
+            "freq" | "wkst" | "byday" => Value::String(v.to_owned()),
+            _ => v.parse::<i64>().map(Value::from).unwrap_or_else(|_| Value::String(v.to_owned())),
+        });
+        let mut values = values.collect::<Vec<_>>();
+        let value = if values.len() == 1 { values.remove(0) } else { Value::Array(values) };
+        rv.insert(key, value);
+    }
+    Value::Object(rv)
+}
+
+fn is_digits(s: &str, len: usize) -> bool {
+    s.len() == len && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// `20240115` to `2024-01-15`. Values of any other shape are kept.
+fn format_date(s: &str) -> String {
+    if is_digits(s, 8) {
+        format!("{}-{}-{}", &s[..4], &s[4..6], &s[6..])
+    } else {
+        s.to_owned()
+    }
+}
+
+/// `133000Z` to `13:30:00Z`. Values of any other shape are kept.
+fn format_time(s: &str) -> String {
+    let (time, utc) = match s.strip_suffix('Z') {
+        Some(t) => (t, "Z"),
+        None => (s, ""),
+    };
+    if is_digits(time, 6) {
+        format!("{}:{}:{}{}", &time[..2], &time[2..4], &time[4..], utc)
+    } else {
+        s.to_owned()
+    }
+}
+
+/// `20240115T133000Z` to `2024-01-15T13:30:00Z`. Values of any other shape are kept.
+fn format_date_time(s: &str) -> String {
+    match s.find('T') {
+        Some(i) if is_digits(&s[..i], 8) => {
+            format!("{}T{}", format_date(&s[..i]), format_time(&s[i + 1..]))
+        },
+        _ => s.to_owned(),
+    }
+}
+
+fn format_date_and_or_time(s: &str) -> String {
+    if let Some(time) = s.strip_prefix('T') {
+        format!("T{}", format_time(time))
+    } else if s.contains('T') {
+        format_date_time(s)
+    } else {
+        format_date(s)
+    }
+}
+
+/// Format both ends of a period, the end being either a date-time or a duration.
+fn format_period(s: &str) -> String {
+    match s.find('/') {
+        Some(i) if s[i + 1..].trim_start_matches(['+', '-']).starts_with('P') => {
+            format!("{}/{}", format_date_time(&s[..i]), &s[i + 1..])
+        },
+        Some(i) => format!("{}/{}", format_date_time(&s[..i]), format_date_time(&s[i + 1..])),
+        None => s.to_owned(),
+    }
+}
+
+/// `-0500` to `-05:00`, and `+013045` to `+01:30:45`. Values of any other shape are kept.
+fn format_utc_offset(s: &str) -> String {
+    if !s.starts_with(['+', '-']) {
+        return s.to_owned();
+    }
+    let (sign, digits) = s.split_at(1);
+    if is_digits(digits, 4) {
+        format!("{}{}:{}", sign, &digits[..2], &digits[2..])
+    } else if is_digits(digits, 6) {
+        format!("{}{}:{}:{}", sign, &digits[..2], &digits[2..4], &digits[4..])
+    } else {
+        s.to_owned()
+    }
+}
+
+/// Build a component from jCal or jCard, the inverse of `to_jcal()` and `to_jcard()`.
+///
+/// A `vcard` array is read as jCard, anything else as jCal. Values are converted back to their
+/// iCalendar or vCard form, and a `VALUE` parameter is only added if the type differs from the
+/// default type of the property. JSON that is not shaped like jCal yields
+/// `VObjectError::InvalidValue`.
+pub fn from_jcal(value: &Value) -> Result<Component, VObjectError> {
+    let format = match value.get(0).and_then(Value::as_str) {
+        Some(name) if name.eq_ignore_ascii_case("vcard") => Format::Jcard,
+        _ => Format::Jcal,
+    };
+    component_from_json(value, format)
+}
+
+fn invalid_jcal(value: &Value) -> VObjectError {
+    invalid("jCal", &value.to_string())
+}
+
+fn component_from_json(value: &Value, format: Format) -> VObjectResult<Component> {
+    let (name, props, subs) = match value.as_array().map(|a| &a[..]) {
+        Some([Value::String(name), Value::Array(props)]) => (name, props, &[][..]),
+        Some([Value::String(name), Value::Array(props), Value::Array(subs)]) => {
+            (name, props, &subs[..])
+        },
+        _ => return Err(invalid_jcal(value)),
+    };
+
+    let mut c = Component::new(&name[..]);
+    for prop in props {
+        c.push(property_from_json(prop, format)?);
+    }
+    for sub in subs {
+        c.add_subcomponent(component_from_json(sub, format)?);
+    }
+    Ok(c)
+}
+
+fn property_from_json(value: &Value, format: Format) -> VObjectResult<Property> {
+    let (name, params, value_type, values) = match value.as_array().map(|a| &a[..]) {
+        Some([Value::String(name), Value::Object(params), Value::String(t), values @ ..])
+            if !values.is_empty() => (name, params, t.to_lowercase(), values),
+        _ => return Err(invalid_jcal(value)),
+    };
+
+    let mut prop = Property::new(name.to_uppercase(), "");
+    for (key, v) in params {
+        let values = match *v {
+            Value::String(ref s) => vec![s.clone()],
+            Value::Array(ref a) => {
+                a.iter()
+                    .map(|v| v.as_str().map(str::to_owned).ok_or_else(|| invalid_jcal(v)))
+                    .collect::<VObjectResult<Vec<_>>>()?
+            },
+            _ => return Err(invalid_jcal(v)),
+        };
+        if key == "group" {
+            prop.prop_group = values.into_iter().next();
+        } else {
+            prop.params.insert(key.to_uppercase(), values);
+        }
+    }
+    if value_type != "unknown" && value_type != format.default_type(name) {
+        prop.params.insert("VALUE".to_owned(), vec![value_type.to_uppercase()]);
+    }
+
+    let untyped = |v: &Value| untyped_value(&value_type, v);
+    prop.raw_value = match values {
+        [Value::Array(components)] if format.is_structured(name) => {
+            let components = components.iter().map(|c| match *c {
+                Value::Array(ref items) => {
+                    items.iter().map(&untyped).collect::<VObjectResult<Vec<_>>>().map(|i| i.join(","))
+                },
+                _ => untyped(c),
+            });
+            components.collect::<VObjectResult<Vec<_>>>()?.join(";")
+        },
+        [Value::Object(rule)] if value_type == "recur" => recur_from_json(rule)?,
+        _ => values.iter().map(untyped).collect::<VObjectResult<Vec<_>>>()?.join(","),
+    };
+    Ok(prop)
+}
+
+/// Convert a single jCal value back to its escaped form.
+fn untyped_value(value_type: &str, value: &Value) -> VObjectResult<String> {
+    let s = match *value {
+        Value::String(ref s) => s,
+        Value::Number(ref n) => return Ok(n.to_string()),
+        Value::Bool(b) => return Ok(if b { "TRUE" } else { "FALSE" }.to_owned()),
+        _ => return Err(invalid_jcal(value)),
+    };
+
+    if value_type == "text" {
+        return Ok(escape_chars(s));
+    }
+    Ok(match formatter(value_type) {
+        Some((format, separators)) => compact(s, format, separators),
+        None => s.clone(),
+    })
+}
+
+/// Remove the separators `format` inserts, if `s` is exactly what `format` would produce.
+fn compact(s: &str, format: fn(&str) -> String, separators: &[char]) -> String {
+    let rv = s.replace(separators, "");
+    if format(&rv) == s { rv } else { s.to_owned() }
+}
+
+fn recur_from_json(rule: &Map<String, Value>) -> VObjectResult<String> {
+    let freq = rule.iter().filter(|&(k, _)| k == "freq");
+    let mut parts = vec![];
+    for (key, value) in freq.chain(rule.iter().filter(|&(k, _)| k != "freq")) {
+        let values = match *value {
+            Value::Array(ref values) => &values[..],
+            _ => ::std::slice::from_ref(value),
+        };
+        let values = values.iter().map(|v| match *v {
+            Value::String(ref s) if key == "until" => {
+                Ok(compact(s, format_date_and_or_time, &['-', ':']))
+            },
+            Value::String(ref s) => Ok(s.clone()),
+            Value::Number(ref n) => Ok(n.to_string()),
+            _ => Err(invalid_jcal(v)),
+        });
+        let values = values.collect::<VObjectResult<Vec<_>>>()?;
+        parts.push(format!("{}={}", key.to_uppercase(), values.join(",")));
+    }
+    Ok(parts.join(";"))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use component::parse_component;
+    use jcal::{from_jcal, to_jcal, to_jcard};
+
+    #[test]
+    fn test_to_jcal() {
+        let c = parse_component("BEGIN:VCALENDAR\r\n\
+                                 VERSION:2.0\r\n\
+                                 BEGIN:VEVENT\r\n\
+                                 UID:1234\r\n\
+                                 DTSTART;TZID=Europe/Vienna:20240115T133000\r\n\
+                                 DTEND;VALUE=DATE:20240116\r\n\
+                                 SUMMARY:Lunch\\, maybe\r\n\
+                                 CATEGORIES:FOOD,WORK\r\n\
+                                 EXDATE:20240122T133000Z,20240129T133000Z\r\n\
+                                 RRULE:FREQ=WEEKLY;COUNT=5;BYDAY=MO,WE\r\n\
+                                 GEO:48.2;16.37\r\n\
+                                 PRIORITY:1\r\n\
+                                 X-FOO;X-PARAM=a,b:bar\r\n\
+                                 END:VEVENT\r\n\
+                                 END:VCALENDAR\r\n").unwrap();
+
+        assert_eq!(to_jcal(&c), json!([
+            "vcalendar",
+            [["version", {}, "text", "2.0"]],
+            [[
+                "vevent",
+                [
+                    ["uid", {}, "text", "1234"],
+                    ["dtstart", {"tzid": "Europe/Vienna"}, "date-time", "2024-01-15T13:30:00"],
+                    ["dtend", {}, "date", "2024-01-16"],
+                    ["summary", {}, "text", "Lunch, maybe"],
+                    ["categories", {}, "text", "FOOD", "WORK"],
+                    ["exdate", {}, "date-time", "2024-01-22T13:30:00Z", "2024-01-29T13:30:00Z"],
+                    ["rrule", {}, "recur", {"freq": "WEEKLY", "count": 5, "byday": ["MO", "WE"]}],
+                    ["geo", {}, "float", [48.2, 16.37]],
+                    ["priority", {}, "integer", 1],
+                    ["x-foo", {"x-param": ["a", "b"]}, "unknown", "bar"]
+                ],
+                []
+            ]]
+        ]));
+    }
+
+    #[test]
+    fn test_to_jcard() {
+        let c = parse_component("BEGIN:VCARD\r\n\
+                                 FN:John Doe\r\n\
+                                 VERSION:4.0\r\n\
+                                 N:Doe;John;;Dr.,Prof.;\r\n\
+                                 item1.TEL;TYPE=work,voice:+1 555 1234\r\n\
+                                 BDAY:19850412\r\n\
+                                 REV:20240115T133000Z\r\n\
+                                 END:VCARD\r\n").unwrap();
+
+        assert_eq!(to_jcard(&c), json!([
+            "vcard",
+            [
+                ["version", {}, "text", "4.0"],
+                ["fn", {}, "text", "John Doe"],
+                ["n", {}, "text", ["Doe", "John", "", ["Dr.", "Prof."], ""]],
+                ["tel", {"group": "item1", "type": ["work", "voice"]}, "text", "+1 555 1234"],
+                ["bday", {}, "date-and-or-time", "1985-04-12"],
+                ["rev", {}, "timestamp", "2024-01-15T13:30:00Z"]
+            ]
+        ]));
+    }
+
+    #[test]
+    fn test_from_jcal_roundtrip() {
+        let inputs = [
+            "BEGIN:VCALENDAR\r\n\
+             VERSION:2.0\r\n\
+             BEGIN:VEVENT\r\n\
+             UID:1234\r\n\
+             DTSTART;TZID=Europe/Vienna:20240115T133000\r\n\
+             DTEND;VALUE=DATE:20240116\r\n\
+             SUMMARY:Lunch\\, maybe\r\n\
+             CATEGORIES:FOOD,WORK\r\n\
+             EXDATE:20240122T133000Z,20240129T133000Z\r\n\
+             RRULE:FREQ=WEEKLY;UNTIL=20240301T000000Z;BYDAY=MO,WE\r\n\
+             GEO:48.2;16.37\r\n\
+             PRIORITY:1\r\n\
+             URL:http://example.com/a,b\r\n\
+             X-FOO;X-PARAM=a,b:bar\\;baz\r\n\
+             BEGIN:VALARM\r\n\
+             TRIGGER:-PT15M\r\n\
+             END:VALARM\r\n\
+             END:VEVENT\r\n\
+             BEGIN:VTIMEZONE\r\n\
+             BEGIN:STANDARD\r\n\
+             TZOFFSETFROM:+0200\r\n\
+             TZOFFSETTO:+0100\r\n\
+             END:STANDARD\r\n\
+             END:VTIMEZONE\r\n\
+             END:VCALENDAR\r\n",
+            "BEGIN:VCARD\r\n\
+             FN:John Doe\r\n\
+             VERSION:4.0\r\n\
+             N:Doe;John;;Dr.,Prof.;\r\n\
+             item1.TEL;TYPE=work,voice:+1 555 1234\r\n\
+             BDAY:19850412\r\n\
+             ANNIVERSARY:--0412\r\n\
+             REV:20240115T133000Z\r\n\
+             END:VCARD\r\n",
+        ];
+
+        for input in &inputs {
+            let c = parse_component(input).unwrap();
+            let json = if c.name == "VCARD" { to_jcard(&c) } else { to_jcal(&c) };
+            assert_eq!(from_jcal(&json).unwrap(), c);
+        }
+    }
+
+    #[test]
+    fn test_from_jcal_invalid() {
+        assert!(from_jcal(&json!({"vcalendar": []})).is_err());
+        assert!(from_jcal(&json!(["vcalendar", [["summary", {}, "text"]], []])).is_err());
+        assert!(from_jcal(&json!(["vcalendar", [["summary", {}, "text", {}]], []])).is_err());
+    }
+}
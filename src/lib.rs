@@ -1,25 +1,78 @@
 #[cfg(feature = "timeconversions")]
 extern crate chrono;
 
+extern crate indexmap;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "jcal")]
+extern crate serde_json;
 extern crate thiserror;
+#[cfg(feature = "uidgeneration")]
+extern crate uuid;
 
 #[macro_use] pub mod param;
 #[macro_use] mod util;
 
 pub mod component;
+mod encoding;
 pub mod error;
+pub mod merge;
 mod parser;
 pub mod property;
+mod validate;
+pub mod reader;
+pub mod timezone;
+pub mod value;
 pub mod vcard;
 pub mod icalendar;
+#[cfg(feature = "jcal")]
+pub mod jcal;
 
 pub use component::Component;
+pub use component::ComponentBuilder;
 pub use component::parse_component;
+pub use component::parse_component_bytes;
+pub use component::parse_component_from_file;
+pub use component::parse_component_with;
+pub use component::parse_components;
+pub use component::parse_components_with;
+pub use component::parse_components_lenient;
 pub use component::read_component;
 pub use component::write_component;
+pub use component::write_component_to;
+pub use component::write_component_to_file;
+pub use component::write_component_to_file_with;
+pub use component::write_component_with;
+pub use component::WriteOptions;
+pub use component::LineEnding;
+pub use component::ParseOptions;
+pub use component::Version;
+pub use error::DecodeError;
+pub use error::ParseError;
+pub use error::ParseErrorKind;
+pub use error::ValidationError;
+pub use merge::diff;
+pub use merge::ComponentDiff;
+pub use merge::MergeStrategy;
+pub use merge::MergeSummary;
+pub use merge::MultiValued;
+pub use merge::PropertyChange;
+pub use param::escape_param_value;
+pub use param::unescape_param_value;
 pub use property::Property;
 pub use property::escape_chars;
+pub use property::escape_chars_cow;
+pub use property::needs_escaping;
 pub use property::unescape_chars;
+pub use property::unescape_chars_cow;
+pub use reader::ComponentReader;
+pub use reader::parse_components_from_reader;
+pub use reader::unfold;
 
 pub use vcard::Vcard;
+pub use vcard::StructuredName;
+pub use vcard::StructuredAddress;
 pub use icalendar::ICalendar;
+#[cfg(feature = "jcal")] pub use jcal::from_jcal;
+#[cfg(feature = "jcal")] pub use jcal::to_jcal;
+#[cfg(feature = "jcal")] pub use jcal::to_jcard;
@@ -0,0 +1,226 @@
+//! Merging one component into another, such as an incoming `VCARD` into a local copy, and
+//! computing the differences between two components.
+
+use component::Component;
+use param::Parameters;
+use property::Property;
+use validate::is_single_valued;
+
+/// How properties that may occur several times are combined by `Component::merge()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MultiValued {
+    /// Keep the existing properties and append those of the other component that are not
+    /// present yet.
+    Union,
+    /// Replace the existing properties with those of the other component.
+    Replace,
+}
+
+/// Options for `Component::merge()`.
+///
+/// Properties that may occur at most once, such as `SUMMARY` in a `VEVENT`, or `N` and, before
+/// vCard 4.0, `FN` in a `VCARD`, are always taken from the other component. All other properties are combined as
+/// given by `multi_valued`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MergeStrategy {
+    pub multi_valued: MultiValued,
+    /// Whether the `UID` of the other component replaces an existing one. A missing `UID` is
+    /// always taken from the other component.
+    pub keep_other_uid: bool,
+}
+
+impl Default for MergeStrategy {
+    fn default() -> MergeStrategy {
+        MergeStrategy {
+            multi_valued: MultiValued::Union,
+            keep_other_uid: false,
+        }
+    }
+}
+
+/// What `Component::merge()` changed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MergeSummary {
+    /// The names of the properties that were added, once per property.
+    pub added: Vec<String>,
+    /// The names of the properties whose values were replaced.
+    pub replaced: Vec<String>,
+    /// The names of the subcomponents that were added.
+    pub subcomponents_added: Vec<String>,
+    /// The subcomponents that were merged into an existing one with the same name and `UID`,
+    /// with what changed in them.
+    pub subcomponents_merged: Vec<(String, MergeSummary)>,
+}
+
+impl MergeSummary {
+    /// Whether nothing was changed at all.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() &&
+            self.replaced.is_empty() &&
+            self.subcomponents_added.is_empty() &&
+            self.subcomponents_merged.iter().all(|(_, s)| s.is_empty())
+    }
+}
+
+pub fn merge(c: &mut Component, other: Component, strategy: MergeStrategy) -> MergeSummary {
+    let mut summary = MergeSummary::default();
+
+    for (key, props) in other.props {
+        if props.is_empty() {
+            continue;
+        }
+        let single = is_single_valued(c, &key);
+        let replace = match c.props.get(&key) {
+            None => false,
+            Some(_) if key == "UID" => strategy.keep_other_uid,
+            Some(_) => single || strategy.multi_valued == MultiValued::Replace,
+        };
+        let existing = c.props.entry(key.clone()).or_default();
+
+        if replace {
+            if *existing != props {
+                *existing = props;
+                summary.replaced.push(key);
+            }
+        } else if existing.is_empty() || !(key == "UID" || single) {
+            for prop in props {
+                if !existing.contains(&prop) {
+                    existing.push(prop);
+                    summary.added.push(key.clone());
+                }
+            }
+        }
+    }
+
+    for sub in other.subcomponents {
+        let uid = sub.get_only("UID").map(|p| p.raw_value.clone());
+        let matching = c.subcomponents.iter().position(|s| {
+            s.name == sub.name && match uid {
+                Some(ref uid) => s.get_only("UID").map(|p| &p.raw_value) == Some(uid),
+                None => *s == sub,
+            }
+        });
+
+        match matching {
+            Some(i) if uid.is_some() => {
+                let name = sub.name.clone();
+                let sub_summary = merge(&mut c.subcomponents[i], sub, strategy);
+                summary.subcomponents_merged.push((name, sub_summary));
+            },
+            // An identical subcomponent without UID is already present.
+            Some(_) => (),
+            None => {
+                summary.subcomponents_added.push(sub.name.clone());
+                c.subcomponents.push(sub);
+            },
+        }
+    }
+
+    summary
+}
+
+/// A property whose value differs between two components, see `diff()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PropertyChange {
+    /// The uppercase name of the property.
+    pub name: String,
+    pub params: Parameters,
+    pub prop_group: Option<String>,
+    /// The unescaped value in the first component.
+    pub old_value: String,
+    /// The unescaped value in the second component.
+    pub new_value: String,
+}
+
+/// The differences between two components, see `diff()`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ComponentDiff {
+    /// Properties only found in the second component.
+    pub added: Vec<Property>,
+    /// Properties only found in the first component.
+    pub removed: Vec<Property>,
+    /// Properties with the same name, parameters and group in both components, but different
+    /// values.
+    pub changed: Vec<PropertyChange>,
+    /// Subcomponents only found in the second component.
+    pub added_subcomponents: Vec<Component>,
+    /// Subcomponents only found in the first component.
+    pub removed_subcomponents: Vec<Component>,
+    /// The differences of subcomponents found in both, by name. Only subcomponents that differ
+    /// are listed.
+    pub changed_subcomponents: Vec<(String, ComponentDiff)>,
+}
+
+impl ComponentDiff {
+    /// Whether the two components are the same.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() &&
+            self.removed.is_empty() &&
+            self.changed.is_empty() &&
+            self.added_subcomponents.is_empty() &&
+            self.removed_subcomponents.is_empty() &&
+            self.changed_subcomponents.is_empty()
+    }
+}
+
+/// Compute what changed from `a` to `b`.
+///
+/// Properties are matched by name, parameters and group, and their unescaped values compared,
+/// so escaping differences do not count as changes. Of several same-named properties, those
+/// with equal values are matched first and the remaining ones in order. Subcomponents are
+/// matched by name and `UID`, or in order among same-named ones without a `UID`. The names of
+/// the components themselves are not compared.
+pub fn diff(a: &Component, b: &Component) -> ComponentDiff {
+    let mut rv = ComponentDiff::default();
+
+    let keys = a.props.keys().chain(b.props.keys().filter(|k| !a.props.contains_key(*k)));
+    for key in keys {
+        let mut old = a.get_all(key).iter().collect::<Vec<_>>();
+        let mut new = b.get_all(key).iter().collect::<Vec<_>>();
+        let same = |x: &Property, y: &Property| x.params == y.params && x.prop_group == y.prop_group;
+
+        // Drop the pairs that did not change at all.
+        old.retain(|o| {
+            match new.iter().position(|n| same(o, n) && o.value_as_string() == n.value_as_string()) {
+                Some(i) => { new.remove(i); false },
+                None => true,
+            }
+        });
+
+        for o in old {
+            match new.iter().position(|n| same(o, n)) {
+                Some(i) => {
+                    let n = new.remove(i);
+                    rv.changed.push(PropertyChange {
+                        name: key.clone(),
+                        params: o.params.clone(),
+                        prop_group: o.prop_group.clone(),
+                        old_value: o.value_as_string(),
+                        new_value: n.value_as_string(),
+                    });
+                },
+                None => rv.removed.push(o.clone()),
+            }
+        }
+        rv.added.extend(new.into_iter().cloned());
+    }
+
+    let uid = |c: &Component| c.get_only("UID").map(|p| p.raw_value.clone());
+    let mut unmatched = b.subcomponents.iter().collect::<Vec<_>>();
+    for old in &a.subcomponents {
+        let old_uid = uid(old);
+        match unmatched.iter().position(|n| n.name == old.name && uid(n) == old_uid) {
+            Some(i) => {
+                let new = unmatched.remove(i);
+                let d = diff(old, new);
+                if !d.is_empty() {
+                    rv.changed_subcomponents.push((old.name.clone(), d));
+                }
+            },
+            None => rv.removed_subcomponents.push(old.clone()),
+        }
+    }
+    rv.added_subcomponents.extend(unmatched.into_iter().cloned());
+
+    rv
+}
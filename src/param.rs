@@ -1,16 +1,73 @@
+use std::borrow::Cow;
 use std::collections::BTreeMap;
 
-pub type Parameters = BTreeMap<String, String>;
+/// Property parameters, mapping each parameter name to its list of values.
+///
+/// A parameter such as `TYPE=work,voice` has two values, one without any `=value` part has
+/// none. The values of a parameter given several times, as in `TYPE=work;TYPE=voice`, are kept
+/// in one list; `Property::param_breaks` records where each occurrence starts.
+///
+/// Parameters are ordered by name, and written in that order, so serialization does not depend
+/// on the order in which they were parsed or set.
+pub type Parameters = BTreeMap<String, Vec<String>>;
 
 #[macro_export]
 macro_rules! parameters(
     { $($key:expr => $value:expr),* } => {
         #[allow(unused_mut)]
         {
-            let mut m : ::std::collections::BTreeMap<String, String> =
+            let mut m : ::std::collections::BTreeMap<String, Vec<String>> =
                 ::std::collections::BTreeMap::new();
-            $( m.insert($key.into(), $value.into()); )*
+            $( m.insert($key.into(), vec![$value.into()]); )*
             m
         }
      };
 );
+
+/// Escape a parameter value as described in RFC 6868: a caret becomes `^^`, a line break `^n`
+/// and a double quote `^'`, none of which could be written otherwise.
+pub fn escape_param_value(s: &str) -> String {
+    escape_param_value_cow(s).into_owned()
+}
+
+pub(crate) fn escape_param_value_cow(s: &str) -> Cow<'_, str> {
+    if !s.contains(['^', '\r', '\n', '"']) {
+        return Cow::Borrowed(s);
+    }
+
+    let mut rv = String::with_capacity(s.len() + 2);
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '^' => rv.push_str("^^"),
+            '\r' if chars.peek() == Some(&'\n') => {
+                chars.next();
+                rv.push_str("^n");
+            },
+            '\r' | '\n' => rv.push_str("^n"),
+            '"' => rv.push_str("^'"),
+            c => rv.push(c),
+        }
+    }
+    Cow::Owned(rv)
+}
+
+/// Unescape a parameter value as described in RFC 6868, the inverse of `escape_param_value`.
+/// A caret followed by anything other than `^`, `n` or `'` is kept as it is.
+pub fn unescape_param_value(s: &str) -> String {
+    if !s.contains('^') {
+        return s.to_owned();
+    }
+
+    let mut rv = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek().cloned()) {
+            ('^', Some('^')) => { chars.next(); rv.push('^'); },
+            ('^', Some('n')) | ('^', Some('N')) => { chars.next(); rv.push('\n'); },
+            ('^', Some('\'')) => { chars.next(); rv.push('"'); },
+            (c, _) => rv.push(c),
+        }
+    }
+    rv
+}
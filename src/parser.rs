@@ -1,13 +1,72 @@
 use std::collections::BTreeMap;
-use std::fmt;
 
 use thiserror::Error;
 
-use component::Component;
+use component::{Component, ParseOptions};
+use param::{unescape_param_value, Parameters};
 use property::Property;
 
-#[derive(Debug, Clone, Error)]
-pub enum ParseErrorReason {
+/// A parse failure, together with the location in the original input where it occurred.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{} at line {}, column {}", kind, line, column)]
+pub struct ParseError {
+    kind: ParseErrorKind,
+    line: usize,
+    column: usize,
+}
+
+impl ParseError {
+    /// Create an error for the given byte offset into `input`.
+    pub(crate) fn at(kind: ParseErrorKind, input: &str, pos: usize) -> ParseError {
+        let before = &input[..pos];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+
+        ParseError {
+            kind,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+
+    /// Shift the reported line by `lines`, for errors in input that started further into a
+    /// larger document.
+    pub(crate) fn offset_lines(mut self, lines: usize) -> ParseError {
+        self.line += lines;
+        self
+    }
+
+    /// The reason why parsing failed.
+    pub fn kind(&self) -> &ParseErrorKind {
+        &self.kind
+    }
+
+    /// The 1-based line in the original (folded) input.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The 1-based column, counted in characters, in the original (folded) input.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Whether this error only follows from `previous` when parsing leniently: an `END` line
+    /// that does not match the current component is then matched against every enclosing one
+    /// and finally the top level, where it would be reported again.
+    pub(crate) fn follows(&self, previous: Option<&ParseError>) -> bool {
+        let previous = match previous {
+            Some(previous) => previous,
+            None => return false,
+        };
+        matches!(self.kind, ParseErrorKind::MismatchedEnd { .. } | ParseErrorKind::ExpectedBegin) &&
+            matches!(previous.kind, ParseErrorKind::MismatchedEnd { .. }) &&
+            (previous.line, previous.column) == (self.line, self.column)
+    }
+}
+
+/// The reason why parsing a component failed.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ParseErrorKind {
     #[error("trailing data: {}", _0)]
     TrailingData(String),
     #[error("expected {}, found EOL", _0)]
@@ -22,22 +81,76 @@ pub enum ParseErrorReason {
     NoParameterName,
     #[error("expected BEGIN tag")]
     ExpectedBegin,
-    #[error("mismatched tags: BEGIN:{} vs END:{}", _0, _1)]
-    MismatchedTag(String, String),
+    #[error("mismatched tags: BEGIN:{} vs END:{}", expected, found)]
+    MismatchedEnd { expected: String, found: String },
+    #[error("unexpected end of input")]
+    UnexpectedEof,
+    #[error("line is {} octets long, more than the allowed 75", _0)]
+    LineTooLong(usize),
 }
 
-type ParseResult<T> = Result<T, ParseErrorReason>;
+type ParseResult<T> = Result<T, ParseErrorKind>;
 
 pub struct Parser<'s> {
     pub input: &'s str,
     pub pos: usize,
+    /// Errors recovered from so far, if the parser is lenient.
+    pub errors: Option<Vec<ParseError>>,
+    pub options: ParseOptions,
+    /// The length of the byte order mark skipped before `input`, to report source spans in
+    /// terms of the original input.
+    pub bom_len: usize,
 }
 
+/// The vCard 2.1 type names that may be given as bare parameters, such as in `TEL;WORK:...`.
+const VCARD21_TYPES: &[&str] = &[
+    "DOM", "INTL", "POSTAL", "PARCEL", "HOME", "WORK", "PREF", "VOICE", "FAX", "MSG", "CELL",
+    "PAGER", "BBS", "MODEM", "CAR", "ISDN", "VIDEO", "INTERNET", "X400",
+];
+
 impl<'s> Parser<'s> {
+    /// Create a parser for `input`. A leading UTF-8 byte order mark is skipped.
     pub fn new(input: &'s str) -> Self {
+        let stripped = strip_bom(input);
         Parser {
-            input: input,
+            input: stripped,
             pos: 0,
+            errors: None,
+            options: ParseOptions::default(),
+            bom_len: input.len() - stripped.len(),
+        }
+    }
+
+    pub fn with_options(input: &'s str, options: &ParseOptions) -> Self {
+        Parser {
+            options: options.clone(),
+            ..Parser::new(input)
+        }
+    }
+
+    /// Fail on the first physical line that is longer than 75 octets, not counting the line
+    /// ending, if `strict_line_length` is set in the options.
+    pub fn check_line_lengths(&self) -> Result<(), ParseError> {
+        if !self.options.strict_line_length {
+            return Ok(());
+        }
+        let mut start = 0;
+        for line in self.input.split('\n') {
+            let len = line.strip_suffix('\r').unwrap_or(line).len();
+            if len > 75 {
+                return Err(ParseError::at(ParseErrorKind::LineTooLong(len), self.input, start));
+            }
+            start += line.len() + 1;
+        }
+        Ok(())
+    }
+
+    /// Create a parser that skips malformed content lines instead of failing, collecting the
+    /// errors in `errors`.
+    pub fn new_lenient(input: &'s str) -> Self {
+        Parser {
+            errors: Some(vec![]),
+            ..Parser::new(input)
         }
     }
 
@@ -47,6 +160,9 @@ impl<'s> Parser<'s> {
     /// without actually
     /// consuming it (immutable self).
     ///
+    /// Only the line break and the single space or tab following it are
+    /// skipped, any further whitespace is content.
+    ///
     /// Return an option for next char, and needed increment to consume it
     /// from current position.
     /// CR characters get always skipped, resulting in CRLF to be simplified as
@@ -60,9 +176,10 @@ impl<'s> Parser<'s> {
             None => None,
             Some('\r') => self.peek_at(at + 1),
             Some('\n') => {
-                match self.peek_at(at + 1) {
-                    Some((' ', offset)) |
-                    Some(('\t', offset)) => self.peek_at(offset),
+                // Only whitespace directly after the line break folds, so that a blank line
+                // or a CR before the whitespace ends the content line.
+                match self.input.as_bytes().get(self.pos + at + 1) {
+                    Some(b' ') | Some(b'\t') => self.peek_at(at + 2),
                     _ => Some(('\n', at + 1)),
                 }
             }
@@ -83,12 +200,12 @@ impl<'s> Parser<'s> {
         let real_c = match self.peek() {
             Some((x, _)) => x,
             None => {
-                return Err(ParseErrorReason::UnexpectedEol(c))
+                return Err(ParseErrorKind::UnexpectedEol(c))
            }
         };
 
         if real_c != c {
-            return Err(ParseErrorReason::UnexpectedChar(c, real_c))
+            return Err(ParseErrorKind::UnexpectedChar(c, real_c))
         };
 
         Ok(())
@@ -115,10 +232,7 @@ impl<'s> Parser<'s> {
 
         let consumed = match self.consume_char() {
             Some('\n') => true,
-            Some('\r') => match self.consume_char() {
-                Some('\n') => true,
-                _ => false,
-            },
+            Some('\r') => matches!(self.consume_char(), Some('\n')),
             _ => false,
         };
 
@@ -126,19 +240,39 @@ impl<'s> Parser<'s> {
             Ok(())
         } else {
             self.pos = start_pos;
-            return Err(ParseErrorReason::ExpectedEol)
+            Err(ParseErrorKind::ExpectedEol)
         }
     }
 
     fn sloppy_terminate_line(&mut self) -> ParseResult<()> {
         if !self.eof() {
             self.consume_eol()?;
-            while let Ok(_) = self.consume_eol() {}
+            while self.consume_eol().is_ok() {}
         };
 
         Ok(())
     }
 
+    /// Skip the rest of the current content line.
+    pub fn skip_line(&mut self) {
+        self.consume_while(|x| x != '\r' && x != '\n');
+        let _ = self.sloppy_terminate_line();
+    }
+
+    /// In lenient mode, record an error at the current position and carry on. Otherwise fail.
+    fn recover(&mut self, kind: ParseErrorKind) -> ParseResult<()> {
+        match self.errors {
+            Some(ref mut errors) => {
+                let error = ParseError::at(kind, self.input, self.pos);
+                if !error.follows(errors.last()) {
+                    errors.push(error);
+                }
+                Ok(())
+            },
+            None => Err(kind),
+        }
+    }
+
     // GR this used to return just a slice from input, but line unfolding
     // makes it contradictory, unless one'd want to rescan everything.
     // Since actually useful calls used to_owned() on the result, which
@@ -175,27 +309,52 @@ impl<'s> Parser<'s> {
     }
 
     pub fn consume_property(&mut self) -> ParseResult<Property> {
+        let start_pos = self.pos;
         let group = self.consume_property_group().ok();
         let name = self.consume_property_name()?;
-        let params = self.consume_params();
+        let mut param_breaks = BTreeMap::new();
+        let params = self.consume_params(&mut param_breaks);
 
         self.assert_char(':')?;
         self.consume_char();
 
-        let value = self.consume_property_value()?;
+        let mut value = self.consume_property_value();
+        let mut end_pos = self.pos;
+        self.sloppy_terminate_line()?;
+
+        // vCard 2.1 quoted-printable values continue on the next line after a soft line break,
+        // without the leading whitespace of regular folding.
+        if is_quoted_printable(&params) {
+            while value.ends_with('=') && !self.eof() {
+                value.pop();
+                value.push_str(&self.consume_property_value());
+                end_pos = self.pos;
+                self.sloppy_terminate_line()?;
+            }
+        }
+
+        // Positions are taken in the folded input, so the span covers the physical lines of
+        // the property as they were read, folds included.
+        let source_span = if self.options.source_spans {
+            Some((start_pos + self.bom_len, end_pos + self.bom_len))
+        } else {
+            None
+        };
 
         Ok(Property {
-            name: name,
-            params: params,
+            name,
+            params,
             raw_value: value,
             prop_group: group,
+            param_breaks,
+            source_span,
         })
     }
 
     fn consume_property_name(&mut self) -> ParseResult<String> {
         let rv = self.consume_while(|x| x == '-' || x.is_alphanumeric());
         if rv.is_empty() {
-            Err(ParseErrorReason::NoPropertyName)
+            Err(ParseErrorKind::NoPropertyName)
         } else {
             Ok(rv)
         }
@@ -220,15 +379,13 @@ impl<'s> Parser<'s> {
         e
     }
 
-    fn consume_property_value(&mut self) -> ParseResult<String> {
-        let rv = self.consume_while(|x| x != '\r' && x != '\n');
-        self.sloppy_terminate_line()?;
-        Ok(rv)
+    fn consume_property_value(&mut self) -> String {
+        self.consume_while(|x| x != '\r' && x != '\n')
     }
 
     fn consume_param_name(&mut self) -> ParseResult<String> {
         self.consume_property_name()
-            .map_err(|_| ParseErrorReason::NoParameterName)
+            .map_err(|_| ParseErrorKind::NoParameterName)
     }
 
     fn consume_param_value(&mut self) -> ParseResult<String> {
@@ -240,63 +397,116 @@ impl<'s> Parser<'s> {
             x > '\u{1F}'
         };
 
-        if self.consume_only_char('"') {
+        let rv = if self.consume_only_char('"') {
             let rv = self.consume_while(qsafe);
             self.assert_char('"')?;
             self.consume_char();
-            Ok(rv)
+            rv
         } else {
-            Ok(self.consume_while(|x| qsafe(x) && x != ';' && x != ':'))
+            self.consume_while(|x| qsafe(x) && x != ';' && x != ':' && x != ',')
+        };
+        Ok(unescape_param_value(&rv))
+    }
+
+    /// Consume a comma-separated list of parameter values, each of which may be quoted.
+    fn consume_param_values(&mut self) -> ParseResult<Vec<String>> {
+        let mut rv = vec![self.consume_param_value()?];
+        while self.consume_only_char(',') {
+            rv.push(self.consume_param_value()?);
         }
+        Ok(rv)
     }
 
-    fn consume_param(&mut self) -> ParseResult<(String, String)> {
+    fn consume_param(&mut self) -> ParseResult<(String, Vec<String>)> {
         let name = self.consume_param_name()?;
         let start_pos = self.pos;
-        let value = if self.consume_only_char('=') {
-            match self.consume_param_value() {
+        let values = if self.consume_only_char('=') {
+            match self.consume_param_values() {
                 Ok(x) => x,
                 Err(e) => { self.pos = start_pos; return Err(e); }
             }
         } else {
-            String::new()
+            vec![]
         };
 
-        Ok((name, value))
+        Ok((name, values))
     }
 
-    fn consume_params(&mut self) -> BTreeMap<String, String> {
-        let mut rv: BTreeMap<String, String> = BTreeMap::new();
+    /// Consume all parameters. The values of a parameter that is given more than once, such as
+    /// in `TEL;TYPE=work;TYPE=voice`, are collected under the first spelling of its name, and
+    /// where each further occurrence starts is recorded in `breaks`. With the `vcard21` option,
+    /// bare type names are collected into `TYPE` the same way, one occurrence each.
+    fn consume_params(&mut self, breaks: &mut BTreeMap<String, Vec<usize>>) -> Parameters {
+        let mut rv = Parameters::new();
         while self.consume_only_char(';') {
-            match self.consume_param() {
-                Ok((name, value)) => { rv.insert(name.to_owned(), value.to_owned()); },
+            let (name, values) = match self.consume_param() {
+                Ok((name, values)) => {
+                    if self.options.vcard21 && values.is_empty() &&
+                        VCARD21_TYPES.iter().any(|t| t.eq_ignore_ascii_case(&name)) {
+                        ("TYPE".to_owned(), vec![name])
+                    } else {
+                        (name, values)
+                    }
+                },
                 Err(_) => break,
+            };
+
+            let key = rv.keys().find(|k| k.eq_ignore_ascii_case(&name)).cloned().unwrap_or(name);
+            let existing = rv.entry(key.clone()).or_default();
+            if !existing.is_empty() && !values.is_empty() {
+                breaks.entry(key).or_default().push(existing.len());
             }
+            existing.extend(values);
         }
         rv
     }
 
-    pub fn consume_component(&mut self) -> ParseResult<Component> {
+    /// Consume one component, reporting failures relative to the original input.
+    pub fn consume_component(&mut self) -> Result<Component, ParseError> {
+        self.consume_component_inner()
+            .map_err(|kind| ParseError::at(kind, self.input, self.pos))
+    }
+
+    /// On error, `self.pos` is left at the location the error should be reported at.
+    fn consume_component_inner(&mut self) -> ParseResult<Component> {
         let start_pos = self.pos;
         let mut property = self.consume_property()?;
-        if property.name != "BEGIN" {
+        if !property.name.eq_ignore_ascii_case("BEGIN") {
             self.pos = start_pos;
-            return Err(ParseErrorReason::ExpectedBegin);
+            return Err(ParseErrorKind::ExpectedBegin);
         };
 
         // Create a component with the name of the BEGIN tag's value
         let mut component = Component::new(property.raw_value);
 
         loop {
+            if self.eof() {
+                self.recover(ParseErrorKind::UnexpectedEof)?;
+                break;
+            }
+
             let previous_pos = self.pos;
-            property = self.consume_property()?;
-            if property.name == "BEGIN" {
+            property = match self.consume_property() {
+                Ok(property) => property,
+                Err(kind) => {
+                    self.recover(kind)?;
+                    self.skip_line();
+                    continue;
+                }
+            };
+
+            if property.name.eq_ignore_ascii_case("BEGIN") {
                 self.pos = previous_pos;
-                component.subcomponents.push(self.consume_component()?);
-            } else if property.name == "END" {
-                if property.raw_value != component.name {
-                    self.pos = start_pos;
-                    return Err(ParseErrorReason::MismatchedTag(component.name, property.raw_value));
+                component.subcomponents.push(self.consume_component_inner()?);
+            } else if property.name.eq_ignore_ascii_case("END") {
+                if !property.raw_value.eq_ignore_ascii_case(&component.name) {
+                    self.pos = previous_pos;
+                    // When lenient, close this component and leave the END line to an
+                    // enclosing one.
+                    self.recover(ParseErrorKind::MismatchedEnd {
+                        expected: component.name.clone(),
+                        found: property.raw_value,
+                    })?;
                 }
 
                 break;
@@ -309,28 +519,59 @@ impl<'s> Parser<'s> {
     }
 }
 
+/// Whether the parameters declare a quoted-printable value, either as `ENCODING=QUOTED-PRINTABLE`
+/// or as the bare vCard 2.1 parameter `QUOTED-PRINTABLE`.
+fn is_quoted_printable(params: &Parameters) -> bool {
+    params.iter().any(|(k, v)| {
+        if k.eq_ignore_ascii_case("ENCODING") {
+            v.iter().any(|e| e.eq_ignore_ascii_case("QUOTED-PRINTABLE"))
+        } else {
+            v.is_empty() && k.eq_ignore_ascii_case("QUOTED-PRINTABLE")
+        }
+    })
+}
+
+/// Remove a single leading UTF-8 byte order mark, as written by many Windows applications.
+pub fn strip_bom(input: &str) -> &str {
+    input.strip_prefix('\u{feff}').unwrap_or(input)
+}
+
 #[cfg(test)]
 mod tests {
     use super::Parser;
 
     #[test]
     fn test_unfold1() {
-        let mut p = Parser{input: "ab\r\n c", pos: 2};
+        let mut p = Parser { pos: 2, ..Parser::new("ab\r\n c") };
         assert_eq!(p.consume_char(), Some('c'));
         assert_eq!(p.pos, 6);
     }
 
     #[test]
     fn test_unfold2() {
-        let mut p = Parser{input: "ab\n\tc\nx", pos: 2};
+        let mut p = Parser { pos: 2, ..Parser::new("ab\n\tc\nx") };
         assert_eq!(p.consume_char(), Some('c'));
         assert_eq!(p.consume_char(), Some('\n'));
         assert_eq!(p.consume_char(), Some('x'));
     }
 
+    #[test]
+    fn test_unfold_removes_one_whitespace() {
+        let mut p = Parser::new("a\r\n  b\n\t c\n \td");
+        assert_eq!(p.consume_while(|_| true), "a b c\td");
+    }
+
+    #[test]
+    fn test_unfold_only_directly_after_line_break() {
+        let mut p = Parser::new("a \r\n b\n\n c");
+        assert_eq!(p.consume_while(|x| x != '\n'), "a b");
+        let mut p = Parser::new("a\r\r\n\tb\r\n\r\n c");
+        assert_eq!(p.consume_while(|x| x != '\n'), "ab");
+    }
+
     #[test]
     fn test_consume_while() {
-        let mut p = Parser{input:"af\n oo:bar", pos: 1};
+        let mut p = Parser { pos: 1, ..Parser::new("af\n oo:bar") };
         assert_eq!(p.consume_while(|x| x != ':'), "foo");
         assert_eq!(p.consume_char(), Some(':'));
         assert_eq!(p.consume_while(|x| x != '\n'), "bar");
@@ -338,7 +579,7 @@ mod tests {
 
     #[test]
     fn test_consume_while2() {
-        let mut p = Parser{input:"af\n oo\n\t:bar", pos: 1};
+        let mut p = Parser { pos: 1, ..Parser::new("af\n oo\n\t:bar") };
         assert_eq!(p.consume_while(|x| x != ':'), "foo");
         assert_eq!(p.consume_char(), Some(':'));
         assert_eq!(p.consume_while(|x| x != '\n'), "bar");
@@ -346,15 +587,22 @@ mod tests {
 
     #[test]
     fn test_consume_while3() {
-        let mut p = Parser{input:"af\n oo:\n bar", pos: 1};
+        let mut p = Parser { pos: 1, ..Parser::new("af\n oo:\n bar") };
         assert_eq!(p.consume_while(|x| x != ':'), "foo");
         assert_eq!(p.consume_char(), Some(':'));
         assert_eq!(p.consume_while(|x| x != '\n'), "bar");
     }
 
+    #[test]
+    fn test_strip_bom() {
+        let p = Parser::new("\u{feff}BEGIN:A\u{feff}");
+        assert_eq!(p.input, "BEGIN:A\u{feff}");
+        assert_eq!(Parser::new("\u{feff}\u{feff}A").input, "\u{feff}A");
+    }
+
     #[test]
     fn test_consume_only_char() {
-        let mut p = Parser{input:"\n \"bar", pos: 0};
+        let mut p = Parser::new("\n \"bar");
         assert!(p.consume_only_char('"'));
         assert_eq!(p.pos, 3);
         assert!(!p.consume_only_char('"'));
@@ -368,24 +616,26 @@ mod tests {
         // Test for infinite loops as well
         use std::sync::mpsc::{channel, RecvTimeoutError};
         use std::time::Duration;
-        use super::ParseErrorReason;
-        let mut p = Parser {input: "BEGIN:a\nBEGIN:b\nEND:a", pos: 0};
+        use super::ParseErrorKind;
+        let mut p = Parser::new("BEGIN:a\nBEGIN:b\nEND:a");
 
         let (tx, rx) = channel();
         ::std::thread::spawn(move|| { tx.send(p.consume_component()) });
 
         match rx.recv_timeout(Duration::from_millis(50)) {
-            Err(RecvTimeoutError::Timeout) => assert!(false),
+            Err(RecvTimeoutError::Timeout) => panic!("parser did not terminate"),
             Ok(Err(e)) => {
-                match e {
-                    ParseErrorReason::MismatchedTag(begin, end) => {
-                        assert_eq!(begin, "b");
-                        assert_eq!(end, "a");
+                assert_eq!(e.line(), 3);
+                match *e.kind() {
+                    ParseErrorKind::MismatchedEnd { ref expected, ref found } => {
+                        // Component names are normalized to uppercase, the END value is not.
+                        assert_eq!(expected, "B");
+                        assert_eq!(found, "a");
                     },
-                    _ => assert!(false),
+                    ref kind => panic!("unexpected error: {:?}", kind),
                 }
             },
-            _ => assert!(false),
+            other => panic!("unexpected result: {:?}", other),
         }
     }
 
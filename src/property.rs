@@ -1,19 +1,56 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::collections::BTreeMap;
 
-#[derive(Clone, Debug)]
+use encoding::{decode_base64, decode_data_uri, decode_quoted_printable, Charset};
+use error::*;
+use param::Parameters;
+#[cfg(feature = "serde")] use serde::{Deserialize, Serialize};
+#[cfg(feature = "timeconversions")] use value::{DateTimeValue, Period};
+use value::{parse_utc_offset, Duration, RRule, ValueType};
+
+#[derive(Clone, Debug, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Property {
     /// Key in component.
     pub name: String,
 
     /// Parameters.
-    pub params: BTreeMap<String, String>,
+    pub params: Parameters,
 
     /// Value as unparsed string.
     pub raw_value: String,
 
     /// Property group. E.g. a contentline like `foo.FN:Markus` would result in the group being
     /// `"foo"`.
-    pub prop_group: Option<String>
+    pub prop_group: Option<String>,
+
+    /// Where a parameter was repeated on the content line: for each parameter name in `params`,
+    /// the indices into its values at which another occurrence starts. So
+    /// `TEL;TYPE=work;TYPE=voice` has `{"TYPE": [1]}`, while `TEL;TYPE=work,voice` has nothing.
+    /// The writer uses this to write the occurrences back separately, unless
+    /// `WriteOptions::combine_type_params` is set.
+    ///
+    /// This is not compared by `==`, as the occurrences have the same meaning as one
+    /// comma-separated list.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "BTreeMap::is_empty"))]
+    pub param_breaks: BTreeMap<String, Vec<usize>>,
+
+    /// The byte range of the property in the parsed input, if parsed with
+    /// `ParseOptions::source_spans`. See there for what the range covers.
+    ///
+    /// This is not compared by `==`, so a parsed property still equals one built by hand.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub source_span: Option<(usize, usize)>,
+}
+
+impl PartialEq for Property {
+    fn eq(&self, other: &Property) -> bool {
+        self.name == other.name &&
+            self.params == other.params &&
+            self.raw_value == other.raw_value &&
+            self.prop_group == other.prop_group
+    }
 }
 
 impl Property {
@@ -24,41 +61,583 @@ impl Property {
     {
         Property {
             name: name.into(),
-            params: BTreeMap::new(),
+            params: Parameters::new(),
             raw_value: escape_chars(value.as_ref()),
-            prop_group: None
+            prop_group: None,
+            param_breaks: BTreeMap::new(),
+            source_span: None,
         }
     }
 
+    /// Build a property from already escaped parts, the inverse of `Property::into_parts`.
+    pub fn from_parts(name: String, params: Parameters, raw_value: String, prop_group: Option<String>)
+        -> Property
+    {
+        Property {
+            name,
+            params,
+            raw_value,
+            prop_group,
+            param_breaks: BTreeMap::new(),
+            source_span: None,
+        }
+    }
+
+    /// Split the property into its name, parameters, raw value and group, for example to
+    /// change some of them and rebuild it with `Property::from_parts`. The source span and
+    /// the `param_breaks` are dropped.
+    pub fn into_parts(self) -> (String, Parameters, String, Option<String>) {
+        (self.name, self.params, self.raw_value, self.prop_group)
+    }
+
     /// Get value as unescaped string.
     pub fn value_as_string(&self) -> String {
         unescape_chars(&self.raw_value)
     }
+
+    /// Whether the value is empty or consists only of whitespace.
+    pub fn is_empty(&self) -> bool {
+        self.raw_value.trim().is_empty()
+    }
+
+    /// The length of the unescaped value in characters, so `a\,b` counts as three.
+    pub fn value_len(&self) -> usize {
+        unescape_chars_cow(&self.raw_value).chars().count()
+    }
+
+    /// Get the value as bytes, decoding it according to the `ENCODING` parameter.
+    ///
+    /// `b` and `BASE64` values, such as embedded photos, are base64-decoded, and
+    /// `QUOTED-PRINTABLE` values (also given as a bare vCard 2.1 parameter) are
+    /// quoted-printable-decoded. Without a known encoding, this returns the bytes of the
+    /// unescaped value.
+    pub fn decode_value(&self) -> Result<Vec<u8>, DecodeError> {
+        let encoding = self.transfer_encoding();
+        if encoding.eq_ignore_ascii_case("b") || encoding.eq_ignore_ascii_case("BASE64") {
+            decode_base64(&self.raw_value)
+        } else if encoding.eq_ignore_ascii_case("QUOTED-PRINTABLE") {
+            decode_quoted_printable(&self.raw_value)
+        } else {
+            Ok(self.value_as_string().into_bytes())
+        }
+    }
+
+    /// Get the binary content of a property such as `PHOTO`, `LOGO`, `KEY` or `ATTACH`,
+    /// whichever way it is embedded.
+    ///
+    /// This decodes base64 given by `ENCODING=b` or `ENCODING=BASE64` (vCard 3.0 and
+    /// iCalendar), quoted-printable (vCard 2.1), and `data:` URIs (vCard 4.0). Returns `None` if
+    /// the value is not embedded data, such as a plain text value, even one with another
+    /// `ENCODING` such as `7BIT`, or an `http:` URI.
+    pub fn binary_data(&self) -> Option<Result<Vec<u8>, DecodeError>> {
+        let encoding = self.transfer_encoding();
+        let is_encoded = ["b", "BASE64", "QUOTED-PRINTABLE"]
+            .iter()
+            .any(|e| encoding.eq_ignore_ascii_case(e));
+        if is_encoded {
+            return Some(self.decode_value());
+        }
+        let is_data_uri = self.raw_value.get(..5).is_some_and(|s| s.eq_ignore_ascii_case("data:"));
+        if is_data_uri {
+            Some(decode_data_uri(&self.value_as_string()).map(|(_, data)| data))
+        } else {
+            None
+        }
+    }
+
+    /// Get the value as text, decoding it according to the `ENCODING` and `CHARSET`
+    /// parameters.
+    ///
+    /// Encoded values are decoded as by `Property::decode_value`, and the bytes are then
+    /// converted from the declared charset, UTF-8 by default. UTF-8, ISO-8859-1 and
+    /// Windows-1252 are supported. Values without an encoding were already read as text and are
+    /// only unescaped, though an unknown charset is still an error.
+    pub fn value_decoded_as_string(&self) -> Result<String, DecodeError> {
+        let charset = Charset::from_name(self.get_param("CHARSET").unwrap_or("UTF-8"))?;
+        let encoding = self.transfer_encoding();
+        if encoding.is_empty() || encoding.eq_ignore_ascii_case("8BIT") {
+            Ok(self.value_as_string())
+        } else {
+            charset.decode(self.decode_value()?)
+        }
+    }
+
+    /// The value of the `ENCODING` parameter, or of a bare vCard 2.1 encoding parameter.
+    fn transfer_encoding(&self) -> &str {
+        match self.get_param("ENCODING") {
+            Some(e) => e,
+            None if self.get_param("QUOTED-PRINTABLE") == Some("") => "QUOTED-PRINTABLE",
+            None => "",
+        }
+    }
+
+    /// The type of the value, as given by the `VALUE` parameter, or else the default type of
+    /// the property.
+    ///
+    /// Defaults are those of iCalendar, then of vCard 4.0, so `UID` is text. `GEO` is a URI if
+    /// its value starts with `geo:` and a float pair otherwise. Unknown properties, such as
+    /// `X-` properties, default to text.
+    pub fn value_type(&self) -> ValueType {
+        if let Some(value) = self.get_param("VALUE") {
+            return ValueType::from_name(value);
+        }
+        if self.name.eq_ignore_ascii_case("GEO") {
+            let uri = self.raw_value.get(..4).is_some_and(|s| s.eq_ignore_ascii_case("geo:"));
+            return if uri { ValueType::Uri } else { ValueType::Float };
+        }
+        ValueType::default_for_icalendar(&self.name)
+            .or_else(|| ValueType::default_for_vcard(&self.name))
+            .unwrap_or(ValueType::Text)
+    }
+
+    /// Get a `DATE` or `DATE-TIME` value, such as that of `DTSTART`, `DTEND` or `DUE`.
+    ///
+    /// See `DateTimeValue::from_property`.
+    #[cfg(feature = "timeconversions")]
+    pub fn value_as_datetime(&self) -> Result<DateTimeValue, VObjectError> {
+        DateTimeValue::from_property(self)
+    }
+
+    /// Get the `DATE` or `DATE-TIME` values of a property such as `RDATE` or `EXDATE`.
+    ///
+    /// See `DateTimeValue::list_from_property`.
+    #[cfg(feature = "timeconversions")]
+    pub fn value_as_date_list(&self) -> Result<Vec<DateTimeValue>, VObjectError> {
+        DateTimeValue::list_from_property(self)
+    }
+
+    /// Get the `PERIOD` values of a property such as `FREEBUSY` or `RDATE;VALUE=PERIOD`.
+    ///
+    /// See `Period::list_from_property`.
+    #[cfg(feature = "timeconversions")]
+    pub fn value_as_periods(&self) -> Result<Vec<Period>, VObjectError> {
+        Period::list_from_property(self)
+    }
+
+    /// Get a `DURATION` value, such as that of `DURATION` or a relative `TRIGGER`.
+    pub fn value_as_duration(&self) -> Result<Duration, VObjectError> {
+        Duration::from_property(self)
+    }
+
+    /// Get the recurrence rule of an `RRULE` property.
+    pub fn value_as_rrule(&self) -> Result<RRule, VObjectError> {
+        RRule::from_property(self)
+    }
+
+    /// Get a `UTC-OFFSET` value, such as that of `TZOFFSETFROM` or `TZOFFSETTO`, in seconds
+    /// east of UTC. The value is `+` or `-` followed by `HHMM` or `HHMMSS`.
+    ///
+    /// Returns `None` if the value is malformed or out of range, including the `-0000` that
+    /// RFC 5545 forbids.
+    pub fn value_as_utc_offset(&self) -> Option<i32> {
+        parse_utc_offset(&self.raw_value)
+    }
+
+    /// Get the latitude and longitude of a `GEO` property, given either as `37.386;-122.082`
+    /// (iCalendar and vCard 3.0) or as a `geo:37.386,-122.082` URI (vCard 4.0). An altitude or
+    /// URI parameters are ignored.
+    ///
+    /// Returns `None` if the value is malformed or the coordinates are out of range.
+    pub fn value_as_geo(&self) -> Option<(f64, f64)> {
+        let value = self.raw_value.trim();
+        let uri = value.get(..4).filter(|s| s.eq_ignore_ascii_case("geo:")).map(|_| &value[4..]);
+        let (lat, lon) = match uri {
+            Some(uri) => {
+                let mut coordinates = uri.split(';').next()?.split(',');
+                let lat_lon = (coordinates.next()?, coordinates.next()?);
+                // An optional altitude may follow.
+                if coordinates.count() > 1 {
+                    return None;
+                }
+                lat_lon
+            },
+            None => {
+                let mut parts = value.split(';');
+                let lat_lon = (parts.next()?, parts.next()?);
+                if parts.next().is_some() {
+                    return None;
+                }
+                lat_lon
+            },
+        };
+
+        let lat = lat.trim().parse::<f64>().ok()?;
+        let lon = lon.trim().parse::<f64>().ok()?;
+        if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) {
+            Some((lat, lon))
+        } else {
+            None
+        }
+    }
+
+    /// Get a structured value, such as that of `N` or `ADR`, as its unescaped components.
+    ///
+    /// The value is split at each `;` that is not escaped as `\;`.
+    pub fn value_as_components(&self) -> Vec<String> {
+        split_unescaped(&self.raw_value, ';')
+            .into_iter()
+            .map(unescape_chars)
+            .collect()
+    }
+
+    /// Get a list value, such as that of `CATEGORIES` or `NICKNAME`, as its unescaped items.
+    ///
+    /// The value is split at each `,` that is not escaped as `\,`.
+    pub fn value_as_list(&self) -> Vec<String> {
+        split_unescaped(&self.raw_value, ',')
+            .into_iter()
+            .map(unescape_chars)
+            .collect()
+    }
+
+    /// Set the value to a list of unescaped items, the inverse of `Property::value_as_list`.
+    pub fn set_list(&mut self, items: &[&str]) {
+        self.raw_value = items.iter()
+            .map(|item| escape_chars(item))
+            .collect::<Vec<_>>()
+            .join(",");
+    }
+
+    /// The preference of the property, from 1 (most preferred) to 100, see RFC 6350,
+    /// section 5.3.
+    ///
+    /// This is the value of a vCard 4.0 `PREF=1` parameter. The vCard 3.0 `TYPE=PREF` and the
+    /// bare vCard 2.1 `PREF` mark the preferred property without a level and count as 1.
+    /// Returns `None` if neither is given or the value is not a number from 1 to 100.
+    pub fn pref(&self) -> Option<u8> {
+        match self.get_param("PREF") {
+            Some("") => Some(1),
+            Some(value) => value.parse().ok().filter(|n| (1..=100).contains(n)),
+            None if self.has_param_value("TYPE", "PREF") => Some(1),
+            None => None,
+        }
+    }
+
+    /// Order properties by preference, see `Property::pref()`: a more preferred property comes
+    /// first, and properties without a preference come last.
+    pub fn cmp_by_pref(&self, other: &Property) -> Ordering {
+        fn rank(p: &Property) -> u8 {
+            p.pref().unwrap_or(u8::MAX)
+        }
+        rank(self).cmp(&rank(other))
+    }
+
+    /// Get the value of a parameter. Parameter names are matched case-insensitively.
+    ///
+    /// If the parameter has several values, only the first one is returned. A parameter without
+    /// any value yields an empty string.
+    pub fn get_param(&self, key: &str) -> Option<&str> {
+        self.param_key(key)
+            .map(|k| self.params[k].first().map(|v| &v[..]).unwrap_or(""))
+    }
+
+    /// Get all values of a parameter, matching its name case-insensitively. A parameter that
+    /// was repeated on the content line, such as `TYPE` in `TEL;TYPE=work;TYPE=voice`, yields
+    /// the values of every occurrence.
+    pub fn get_params_all(&self, key: &str) -> Vec<&str> {
+        self.params
+            .iter()
+            .filter(|&(k, _)| k.eq_ignore_ascii_case(key))
+            .flat_map(|(_, values)| values.iter().map(|v| &v[..]))
+            .collect()
+    }
+
+    /// Whether a parameter has the given value, comparing case-insensitively, such as
+    /// `has_param_value("TYPE", "work")` for `TEL;TYPE=WORK,voice`. Every occurrence of the
+    /// parameter and every item of a `,`-separated list is checked, including a quoted list
+    /// such as `TYPE="work,voice"`.
+    pub fn has_param_value(&self, key: &str, value: &str) -> bool {
+        self.get_params_all(key)
+            .iter()
+            .flat_map(|v| v.split(','))
+            .any(|v| v.trim().eq_ignore_ascii_case(value))
+    }
+
+    /// The `LANGUAGE` parameter, a language tag such as `de` or `en-US`.
+    pub fn language(&self) -> Option<&str> {
+        self.get_param("LANGUAGE")
+    }
+
+    /// The `ALTREP` parameter, the URI of an alternate representation of the value, such as
+    /// `CID:part3.msg.970415T083000@example.com`.
+    pub fn altrep(&self) -> Option<&str> {
+        self.get_param("ALTREP")
+    }
+
+    /// Set a parameter to a single value, replacing any parameter of the same (case-insensitive)
+    /// name.
+    pub fn set_param(&mut self, key: &str, value: &str) {
+        self.remove_param(key);
+        self.params.insert(key.to_owned(), vec![value.to_owned()]);
+    }
+
+    /// Chainable variant of `Property::set_param()`, such as
+    /// `Property::new("TEL", "123").with_param("TYPE", "work")`.
+    pub fn with_param(mut self, key: &str, value: &str) -> Self {
+        self.set_param(key, value);
+        self
+    }
+
+    /// Put the property into a group, such as the `item1` of `item1.TEL`.
+    pub fn with_group(mut self, group: &str) -> Self {
+        self.prop_group = Some(group.to_owned());
+        self
+    }
+
+    /// Remove a parameter by its case-insensitive name, returning its values.
+    pub fn remove_param(&mut self, key: &str) -> Option<Vec<String>> {
+        let k = self.param_key(key)?.clone();
+        self.param_breaks.remove(&k);
+        self.params.remove(&k)
+    }
+
+    /// The values of a parameter split into its occurrences on the content line, see
+    /// `Property::param_breaks`. Breaks that do not fit the values, for example after changing
+    /// `params` directly, are ignored.
+    pub(crate) fn param_occurrences<'a>(&'a self, key: &str, values: &'a [String])
+        -> Vec<&'a [String]>
+    {
+        let mut rv = vec![];
+        let mut start = 0;
+        for &i in self.param_breaks.get(key).map(|b| &b[..]).unwrap_or(&[]) {
+            if i > start && i < values.len() {
+                rv.push(&values[start..i]);
+                start = i;
+            }
+        }
+        rv.push(&values[start..]);
+        rv
+    }
+
+    fn param_key(&self, key: &str) -> Option<&String> {
+        self.params.keys().find(|k| k.eq_ignore_ascii_case(key))
+    }
+}
+
+/// Split an escaped value at each `sep` that is not preceded by an escaping backslash. The
+/// parts are returned still escaped.
+pub(crate) fn split_unescaped(s: &str, sep: char) -> Vec<&str> {
+    let mut rv = vec![];
+    let mut start = 0;
+    let mut chars = s.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if c == sep {
+            rv.push(&s[start..i]);
+            start = i + c.len_utf8();
+        }
+    }
+    rv.push(&s[start..]);
+    rv
 }
 
 /// Escape text for a VObject property value.
+///
+/// Backslashes, semicolons, commas and line breaks are escaped, so that
+/// `unescape_chars(&escape_chars(s)) == s`, and the result never contains a raw CR or LF. The
+/// one intentional exception: a CRLF and a lone CR are line breaks, too, and are escaped the
+/// same way as a LF, so they come back as `\n`.
 pub fn escape_chars(s: &str) -> String {
-    // Order matters! Lifted from icalendar.parser
-    // https://github.com/collective/icalendar/
-    s
-        .replace("\\N", "\n")
-        .replace("\\", "\\\\")
-        .replace(";", "\\;")
-        .replace(",", "\\,")
-        .replace("\r\n", "\\n")
-        .replace("\n", "\\n")
+    escape_chars_cow(s).into_owned()
+}
+
+/// Whether `escape_chars()` would change `s`.
+pub fn needs_escaping(s: &str) -> bool {
+    s.contains(['\\', ';', ',', '\n', '\r'])
+}
+
+/// Like `escape_chars()`, but without allocating if nothing needs to be escaped.
+pub fn escape_chars_cow(s: &str) -> Cow<'_, str> {
+    if !needs_escaping(s) {
+        return Cow::Borrowed(s);
+    }
+
+    let mut rv = String::with_capacity(s.len() + s.len() / 8);
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' if chars.peek() == Some(&'\n') => {
+                chars.next();
+                rv.push_str("\\n");
+            },
+            '\\' => rv.push_str("\\\\"),
+            ';' => rv.push_str("\\;"),
+            ',' => rv.push_str("\\,"),
+            '\n' | '\r' => rv.push_str("\\n"),
+            c => rv.push(c),
+        }
+    }
+    Cow::Owned(rv)
 }
 
 /// Unescape text from a VObject property value.
+///
+/// `\n` and `\N` become a newline, `\\`, `\;` and `\,` the escaped character. A backslash
+/// followed by anything else is kept as it is.
 pub fn unescape_chars(s: &str) -> String {
-    // Order matters! Lifted from icalendar.parser
-    // https://github.com/collective/icalendar/
-    s
-        .replace("\\N", "\\n")
-        .replace("\r\n", "\n")
-        .replace("\\n", "\n")
-        .replace("\\,", ",")
-        .replace("\\;", ";")
-        .replace("\\\\", "\\")
+    unescape_chars_cow(s).into_owned()
 }
 
+/// Like `unescape_chars()`, but without allocating if there is nothing to unescape.
+pub fn unescape_chars_cow(s: &str) -> Cow<'_, str> {
+    if !s.contains(['\\', '\r']) {
+        return Cow::Borrowed(s);
+    }
+
+    let mut rv = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.peek().cloned() {
+                Some('n') | Some('N') => {
+                    chars.next();
+                    rv.push('\n');
+                },
+                Some(x @ '\\') | Some(x @ ';') | Some(x @ ',') => {
+                    chars.next();
+                    rv.push(x);
+                },
+                _ => rv.push('\\'),
+            },
+            '\r' if chars.peek() == Some(&'\n') => {
+                chars.next();
+                rv.push('\n');
+            },
+            c => rv.push(c),
+        }
+    }
+    Cow::Owned(rv)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::{escape_chars, escape_chars_cow, needs_escaping, split_unescaped, unescape_chars,
+                unescape_chars_cow, Property};
+
+    #[test]
+    fn test_escape_roundtrip() {
+        let atoms = ["", "a", "\\", "N", "n", ";", ",", "\r", "\n", ":", "ü", "\"", " "];
+        let mut corpus = vec![
+            String::from("Hello, World; with \\backslash\\N and\r\nnewlines\n"),
+            String::from("\\\\\\N\\\\n\\\\\\,\\\\;\r\r\n\r\\N"),
+        ];
+        // Every combination of up to four atoms
+        for a in atoms.iter() {
+            for b in atoms.iter() {
+                for c in atoms.iter() {
+                    for d in atoms.iter() {
+                        corpus.push(format!("{}{}{}{}", a, b, c, d));
+                    }
+                }
+            }
+        }
+
+        for s in &corpus {
+            let escaped = escape_chars(s);
+            assert_eq!(needs_escaping(s), escaped != *s);
+            assert!(!escaped.contains(['\r', '\n']), "{:?} escaped to {:?}", s, escaped);
+            let expected = s.replace("\r\n", "\n").replace('\r', "\n");
+            assert_eq!(&unescape_chars(&escaped), &expected, "escaped {:?}", escaped);
+        }
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape_chars("a\\Nb"), "a\\\\Nb");
+        assert_eq!(escape_chars("a\\nb"), "a\\\\nb");
+        assert_eq!(escape_chars("a\r\nb\nc"), "a\\nb\\nc");
+        assert_eq!(escape_chars("a\rb\r\r\nc"), "a\\nb\\n\\nc");
+        assert_eq!(escape_chars("Doe, John; Jr."), "Doe\\, John\\; Jr.");
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape_chars("a\\Nb\\nc"), "a\nb\nc");
+        assert_eq!(unescape_chars("a\\\\Nb"), "a\\Nb");
+        assert_eq!(unescape_chars("a\\\\\\nb"), "a\\\nb");
+        assert_eq!(unescape_chars("Doe\\, John\\; Jr."), "Doe, John; Jr.");
+        assert_eq!(unescape_chars("C:\\Windows\\"), "C:\\Windows\\");
+    }
+
+    #[test]
+    fn test_cow() {
+        assert!(!needs_escaping("Hello World"));
+        assert!(needs_escaping("Hello, World"));
+        assert!(matches!(escape_chars_cow("Hello World"), Cow::Borrowed("Hello World")));
+        assert_eq!(escape_chars_cow("a;b"), "a\\;b");
+        assert!(matches!(unescape_chars_cow("Hello, World"), Cow::Borrowed("Hello, World")));
+        assert_eq!(unescape_chars_cow("a\\;b"), "a;b");
+    }
+
+    #[test]
+    fn test_debug_is_stable() {
+        let mut prop = Property::new("TEL", "+49 221 9999123");
+        prop.set_param("VALUE", "text");
+        prop.set_param("TYPE", "work");
+        prop.set_param("PREF", "1");
+        assert_eq!(format!("{:?}", prop),
+                   "Property { name: \"TEL\", \
+                    params: {\"PREF\": [\"1\"], \"TYPE\": [\"work\"], \"VALUE\": [\"text\"]}, \
+                    raw_value: \"+49 221 9999123\", prop_group: None, param_breaks: {}, \
+                    source_span: None }");
+    }
+
+    #[test]
+    fn test_is_empty_and_value_len() {
+        let prop = |raw: &str| Property { raw_value: raw.to_owned(), ..Property::new("NOTE", "") };
+
+        assert!(prop("").is_empty());
+        assert!(prop(" \t ").is_empty());
+        assert!(!prop("\\,").is_empty());
+        assert!(!prop("\\n").is_empty());
+        assert!(!prop(" x ").is_empty());
+
+        assert_eq!(prop("").value_len(), 0);
+        assert_eq!(prop("  ").value_len(), 2);
+        assert_eq!(prop("\\,\\;\\\\").value_len(), 3);
+        assert_eq!(prop("a\\nb").value_len(), 3);
+        assert_eq!(prop("Grüße").value_len(), 5);
+    }
+
+    #[test]
+    fn test_with_param_and_group() {
+        let prop = Property::new("TEL", "123")
+            .with_param("TYPE", "home")
+            .with_param("type", "work")
+            .with_param("PREF", "1")
+            .with_group("item1");
+        assert_eq!(prop.get_params_all("TYPE"), ["work"]);
+        assert_eq!(prop.get_param("PREF"), Some("1"));
+        assert_eq!(prop.prop_group.as_deref(), Some("item1"));
+        assert_eq!(prop.raw_value, "123");
+    }
+
+    #[test]
+    fn test_parts() {
+        let mut prop = Property::new("EMAIL", "erika@example.com");
+        prop.set_param("TYPE", "work");
+        prop.prop_group = Some("item1".to_owned());
+
+        let (name, mut params, raw_value, prop_group) = prop.clone().into_parts();
+        assert_eq!(name, "EMAIL");
+        assert_eq!(raw_value, "erika@example.com");
+        assert_eq!(prop_group.as_deref(), Some("item1"));
+        assert_eq!(Property::from_parts(name.clone(), params.clone(), raw_value, prop_group.clone()), prop);
+
+        params.clear();
+        let scrubbed = Property::from_parts(name, params, "redacted".to_owned(), prop_group);
+        assert_eq!(scrubbed.value_as_string(), "redacted");
+        assert!(scrubbed.params.is_empty());
+    }
+
+    #[test]
+    fn test_split_unescaped() {
+        assert_eq!(split_unescaped("", ';'), vec![""]);
+        assert_eq!(split_unescaped("a;b\\;c;", ';'), vec!["a", "b\\;c", ""]);
+        assert_eq!(split_unescaped("a\\\\;b", ';'), vec!["a\\\\", "b"]);
+        assert_eq!(split_unescaped("ü,ö\\,ä", ','), vec!["ü", "ö\\,ä"]);
+    }
+}
@@ -0,0 +1,165 @@
+use std::io::{BufRead, BufReader, Read};
+
+use component::{parse_component_with, Component, ParseOptions};
+use error::*;
+use parser::strip_bom;
+
+/// How much of the start of each unfolded line is kept to recognize `BEGIN:` and `END:`.
+const HEAD_LEN: usize = 6;
+
+/// An iterator over the top-level components of a reader, such as a large `.ics` file.
+///
+/// Only the text of the component currently being read is held in memory. Each component is
+/// parsed as soon as its `END` line has been read, and errors report lines relative to the
+/// whole input. A parse error in one component does not stop the iteration; a failure to read
+/// from the underlying reader does.
+pub struct ComponentReader<R> {
+    reader: BufReader<R>,
+    lookahead: Option<String>,
+    line_no: usize,
+    failed: bool,
+    options: ParseOptions,
+}
+
+impl<R: Read> ComponentReader<R> {
+    pub fn new(reader: R) -> ComponentReader<R> {
+        ComponentReader::with_options(reader, &ParseOptions::default())
+    }
+
+    /// Create a reader that parses each component with the given options.
+    pub fn with_options(reader: R, options: &ParseOptions) -> ComponentReader<R> {
+        ComponentReader {
+            reader: BufReader::new(reader),
+            lookahead: None,
+            line_no: 0,
+            failed: false,
+            options: options.clone(),
+        }
+    }
+
+    /// Read the next physical line, including its line ending.
+    fn next_line(&mut self) -> VObjectResult<Option<String>> {
+        if let Some(line) = self.lookahead.take() {
+            return Ok(Some(line));
+        }
+
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        self.line_no += 1;
+        if self.line_no == 1 && line.starts_with('\u{feff}') {
+            line = strip_bom(&line).to_owned();
+        }
+        Ok(Some(line))
+    }
+
+    fn read_next(&mut self) -> VObjectResult<Option<Component>> {
+        let mut buf = String::new();
+        let mut start_line = 0;
+        let mut depth = 0usize;
+        let mut head = String::new();
+
+        loop {
+            let line = self.next_line()?;
+
+            if let Some(ref l) = line {
+                if !buf.is_empty() && l.starts_with([' ', '\t']) {
+                    if head.len() < HEAD_LEN {
+                        head.extend(trim_eol(&l[1..]).chars().take(HEAD_LEN));
+                    }
+                    buf.push_str(l);
+                    continue;
+                }
+            }
+
+            // The previous logical line is complete.
+            if !buf.is_empty() {
+                if starts_with_ignore_case(&head, "BEGIN:") {
+                    depth += 1;
+                } else if starts_with_ignore_case(&head, "END:") {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        self.lookahead = line;
+                        return self.parse_buffered(&buf, start_line).map(Some);
+                    }
+                }
+            }
+
+            let line = match line {
+                Some(l) => l,
+                None if buf.is_empty() => return Ok(None),
+                None => return self.parse_buffered(&buf, start_line).map(Some),
+            };
+
+            if buf.is_empty() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                start_line = self.line_no;
+            }
+
+            head.clear();
+            head.extend(trim_eol(&line).chars().take(HEAD_LEN));
+            buf.push_str(&line);
+        }
+    }
+
+    fn parse_buffered(&self, buf: &str, start_line: usize) -> VObjectResult<Component> {
+        parse_component_with(buf, &self.options).map_err(|e| e.offset_lines(start_line - 1).into())
+    }
+}
+
+impl<R: Read> Iterator for ComponentReader<R> {
+    type Item = Result<Component, VObjectError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        match self.read_next() {
+            Ok(c) => c.map(Ok),
+            Err(e) => {
+                if let VObjectError::Io { .. } = e {
+                    self.failed = true;
+                }
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Parse all components from a reader, reading one component at a time instead of the whole
+/// input at once.
+///
+/// This accepts the same input as `vobject::parse_components`. Errors are either
+/// `VObjectError::Parse`, or `VObjectError::Io` if reading failed or the input is not valid
+/// UTF-8.
+pub fn parse_components_from_reader<R: Read>(r: R) -> Result<Vec<Component>, VObjectError> {
+    ComponentReader::new(r).collect()
+}
+
+/// Join folded physical lines into logical content lines, one at a time.
+///
+/// The lines may come with or without their line ending, either `\r\n` or `\n`; it is removed
+/// from the yielded lines. A line starting with a space or tab continues the previous one,
+/// without that first character.
+pub fn unfold<I: Iterator<Item = String>>(lines: I) -> impl Iterator<Item = String> {
+    let mut lines = lines.peekable();
+    ::std::iter::from_fn(move || {
+        let mut rv = trim_eol(&lines.next()?).to_owned();
+        while let Some(next) = lines.next_if(|l| l.starts_with([' ', '\t'])) {
+            rv.push_str(trim_eol(&next[1..]));
+        }
+        Some(rv)
+    })
+}
+
+fn trim_eol(line: &str) -> &str {
+    line.trim_end_matches(['\r', '\n'])
+}
+
+fn starts_with_ignore_case(s: &str, prefix: &str) -> bool {
+    s.len() >= prefix.len() && s.as_bytes()[..prefix.len()].eq_ignore_ascii_case(prefix.as_bytes())
+}
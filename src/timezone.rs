@@ -0,0 +1,270 @@
+//! Finding the UTC offset in effect at a local time from a `VTIMEZONE` component.
+//!
+//! Each `STANDARD` and `DAYLIGHT` observance of a `VTIMEZONE` starts at its `DTSTART` and
+//! possibly recurs by `RDATE` or `RRULE`. The offset in effect at a local time is the
+//! `TZOFFSETTO` of the observance with the latest onset before it. This does not evaluate
+//! recurrence rules in general: only the yearly rules used for daylight saving time, such as
+//! `FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU`, are understood, and their `COUNT` is ignored.
+//! Observances with other rules only start at their `DTSTART` and `RDATE`s.
+
+use component::Component;
+use value::{parse_utc_offset, Frequency, RRule, WeekdayNum};
+
+/// A local date and time, ordered chronologically.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct LocalTime {
+    year: i32,
+    month: u32,
+    day: u32,
+    seconds: u32,
+}
+
+/// Read a `DATE` or `DATE-TIME` value, ignoring a trailing `Z`.
+fn parse_local(s: &str) -> Option<LocalTime> {
+    let s = s.trim().trim_end_matches('Z');
+    let (date, time) = match s.find('T') {
+        Some(i) => (&s[..i], &s[i + 1..]),
+        None => (s, "000000"),
+    };
+    let digits = |s: &str, len| s.len() == len && s.bytes().all(|b| b.is_ascii_digit());
+    if !digits(date, 8) || !digits(time, 6) {
+        return None;
+    }
+
+    let num = |s: &str| s.parse::<u32>().ok();
+    Some(LocalTime {
+        year: date[..4].parse().ok()?,
+        month: num(&date[4..6])?,
+        day: num(&date[6..])?,
+        seconds: num(&time[..2])? * 3600 + num(&time[2..4])? * 60 + num(&time[4..])?,
+    })
+}
+
+/// The UTC offset in seconds in effect at the local time `local`, a `DATE-TIME` value such as
+/// `20240115T133000`, according to the `VTIMEZONE` component `vtimezone`.
+///
+/// Before the first observance, its `TZOFFSETFROM` applies. Returns `None` if `local` cannot be
+/// read or the timezone has no usable observance. See the module documentation for which
+/// recurrence rules are understood.
+pub fn utc_offset_at(vtimezone: &Component, local: &str) -> Option<i32> {
+    let local = parse_local(local)?;
+    let observances = vtimezone.subcomponents
+        .iter()
+        .filter(|c| c.name == "STANDARD" || c.name == "DAYLIGHT");
+
+    let mut current: Option<(LocalTime, &Component)> = None;
+    let mut first: Option<(LocalTime, &Component)> = None;
+    for observance in observances {
+        let start = match observance.get_only("DTSTART").and_then(|p| parse_local(&p.raw_value)) {
+            Some(t) => t,
+            None => continue,
+        };
+        if first.is_none_or(|(t, _)| start < t) {
+            first = Some((start, observance));
+        }
+        if let Some(onset) = last_onset(observance, start, local) {
+            if current.is_none_or(|(t, _)| onset > t) {
+                current = Some((onset, observance));
+            }
+        }
+    }
+
+    match current {
+        Some((_, observance)) => offset(observance, "TZOFFSETTO"),
+        None => offset(first?.1, "TZOFFSETFROM"),
+    }
+}
+
+fn offset(observance: &Component, name: &str) -> Option<i32> {
+    parse_utc_offset(&observance.get_only(name)?.raw_value)
+}
+
+/// The latest onset of an observance at or before `local`.
+fn last_onset(observance: &Component, start: LocalTime, local: LocalTime) -> Option<LocalTime> {
+    if start > local {
+        return None;
+    }
+
+    let mut onset = start;
+    let rdates = observance.get_all("RDATE")
+        .iter()
+        .flat_map(|p| p.raw_value.split(','))
+        .filter_map(parse_local);
+    for t in rdates {
+        if t <= local && t > onset {
+            onset = t;
+        }
+    }
+
+    let rule = observance.get_only("RRULE").and_then(|p| p.value_as_rrule().ok());
+    if let Some(t) = rule.and_then(|r| yearly_onset(&r, start, local)) {
+        if t > onset {
+            onset = t;
+        }
+    }
+    Some(onset)
+}
+
+/// The latest occurrence of a yearly rule at or before `local`, if the rule is one of the
+/// simple forms used for daylight saving time.
+fn yearly_onset(rule: &RRule, start: LocalTime, local: LocalTime) -> Option<LocalTime> {
+    if rule.freq != Frequency::Yearly || rule.interval.unwrap_or(1) != 1 {
+        return None;
+    }
+    let month = match rule.by_month[..] {
+        [] => start.month,
+        [m] if m > 0 => m as u32,
+        _ => return None,
+    };
+    if !(1..=12).contains(&month) {
+        return None;
+    }
+    let until = rule.until.as_ref().and_then(|u| parse_local(u));
+
+    for &year in &[local.year, local.year - 1] {
+        let last = days_in_month(year, month);
+        let day = match (&rule.by_day[..], &rule.by_month_day[..]) {
+            ([], []) => start.day,
+            ([], &[d]) if d > 0 && d as u32 <= last => d as u32,
+            (&[wd], []) => nth_weekday(year, month, wd)?,
+            // The second Sunday written as BYMONTHDAY=8,9,10,11,12,13,14;BYDAY=SU.
+            (&[wd], days) if wd.ordinal.is_none() => {
+                let days = days.iter().filter(|&&d| d > 0 && d as u32 <= last).map(|&d| d as u32);
+                days.filter(|&d| weekday(year, month, d) == wd.weekday as u32).min()?
+            },
+            _ => return None,
+        };
+        let t = LocalTime { year, month, day, seconds: start.seconds };
+        if t <= local && t >= start && until.is_none_or(|u| t <= u) {
+            return Some(t);
+        }
+    }
+    None
+}
+
+/// The day of the week of a date, counting from Monday as 0.
+fn weekday(year: i32, month: u32, day: u32) -> u32 {
+    // Sakamoto's method, which counts from Sunday.
+    const T: [i32; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+    let y = if month < 3 { year - 1 } else { year };
+    let sunday_based = (y + y / 4 - y / 100 + y / 400 + T[month as usize - 1] + day as i32) % 7;
+    (sunday_based as u32 + 6) % 7
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// The day of the month of an entry such as `2SU` or `-1SU`.
+fn nth_weekday(year: i32, month: u32, wd: WeekdayNum) -> Option<u32> {
+    if !(1..=12).contains(&month) {
+        return None;
+    }
+    // A month has at most five of any weekday.
+    let n = match wd.ordinal {
+        Some(n) if n != 0 && n.unsigned_abs() <= 5 => n,
+        _ => return None,
+    };
+    let last = days_in_month(year, month);
+    let target = wd.weekday as u32;
+    let day = if n > 0 {
+        let first = 1 + (target + 7 - weekday(year, month, 1)) % 7;
+        first + 7 * (n as u32 - 1)
+    } else {
+        let last_match = last - (weekday(year, month, last) + 7 - target) % 7;
+        last_match.checked_sub(7 * (n.unsigned_abs() - 1))?
+    };
+    if day >= 1 && day <= last { Some(day) } else { None }
+}
+
+#[cfg(test)]
+mod tests {
+    use component::parse_component;
+    use super::{utc_offset_at, weekday};
+
+    #[test]
+    fn test_weekday() {
+        assert_eq!(weekday(2024, 1, 15), 0);
+        assert_eq!(weekday(2024, 3, 31), 6);
+        assert_eq!(weekday(2000, 2, 29), 1);
+    }
+
+    #[test]
+    fn test_utc_offset_at() {
+        let tz = parse_component("BEGIN:VTIMEZONE\r\n\
+                                  TZID:Europe/Vienna\r\n\
+                                  BEGIN:DAYLIGHT\r\n\
+                                  DTSTART:19810329T020000\r\n\
+                                  TZOFFSETFROM:+0100\r\n\
+                                  TZOFFSETTO:+0200\r\n\
+                                  RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU\r\n\
+                                  END:DAYLIGHT\r\n\
+                                  BEGIN:STANDARD\r\n\
+                                  DTSTART:19961027T030000\r\n\
+                                  TZOFFSETFROM:+0200\r\n\
+                                  TZOFFSETTO:+0100\r\n\
+                                  RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU\r\n\
+                                  END:STANDARD\r\n\
+                                  END:VTIMEZONE\r\n").unwrap();
+
+        assert_eq!(utc_offset_at(&tz, "20240115T120000"), Some(3600));
+        assert_eq!(utc_offset_at(&tz, "20240331T015959"), Some(3600));
+        assert_eq!(utc_offset_at(&tz, "20240331T020000"), Some(7200));
+        assert_eq!(utc_offset_at(&tz, "20240715T120000"), Some(7200));
+        assert_eq!(utc_offset_at(&tz, "20241027T030000"), Some(3600));
+        assert_eq!(utc_offset_at(&tz, "20240101"), Some(3600));
+        // Before the first observance.
+        assert_eq!(utc_offset_at(&tz, "19800101T000000"), Some(3600));
+        assert_eq!(utc_offset_at(&tz, "tomorrow"), None);
+    }
+
+    #[test]
+    fn test_utc_offset_at_fixed() {
+        let tz = parse_component("BEGIN:VTIMEZONE\r\n\
+                                  TZID:Asia/Kolkata\r\n\
+                                  BEGIN:STANDARD\r\n\
+                                  DTSTART:19451015T000000\r\n\
+                                  TZOFFSETFROM:+0630\r\n\
+                                  TZOFFSETTO:+0530\r\n\
+                                  END:STANDARD\r\n\
+                                  END:VTIMEZONE\r\n").unwrap();
+
+        assert_eq!(utc_offset_at(&tz, "20240115T120000"), Some(19800));
+        assert_eq!(utc_offset_at(&tz, "19400101T000000"), Some(23400));
+    }
+
+    #[test]
+    fn test_utc_offset_at_malformed_rules() {
+        // Rules that cannot occur are ignored instead of panicking, leaving only the DTSTART
+        // onset of the observance.
+        let rules = [
+            "BYMONTH=0;BYDAY=SU;BYMONTHDAY=8,9,10,11,12,13,14",
+            "BYMONTH=13;BYDAY=SU;BYMONTHDAY=8,9,10,11,12,13,14",
+            "BYMONTH=-3;BYDAY=-1SU",
+            "BYMONTH=13;BYDAY=-1SU",
+            "BYMONTH=2;BYMONTHDAY=31",
+            "BYMONTH=2;BYDAY=SU;BYMONTHDAY=30,31",
+            "BYMONTH=3;BYDAY=-2147483648SU",
+            "BYMONTH=3;BYDAY=2147483647SU",
+            "BYMONTH=3;BYDAY=6SU",
+            "BYMONTH=3;BYDAY=-6SU",
+        ];
+        for rule in &rules {
+            let tz = parse_component(&format!("BEGIN:VTIMEZONE\r\n\
+                                               TZID:Broken\r\n\
+                                               BEGIN:DAYLIGHT\r\n\
+                                               DTSTART:19810329T020000\r\n\
+                                               TZOFFSETFROM:+0100\r\n\
+                                               TZOFFSETTO:+0200\r\n\
+                                               RRULE:FREQ=YEARLY;{}\r\n\
+                                               END:DAYLIGHT\r\n\
+                                               END:VTIMEZONE\r\n", rule)).unwrap();
+            assert_eq!(utc_offset_at(&tz, "20240715T120000"), Some(7200), "{}", rule);
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Required-property and cardinality rules for well-known component types.
+
+use component::{Component, Version};
+use error::ValidationError;
+
+/// The rules for one component type.
+struct Rules {
+    /// Properties that must occur exactly once.
+    required: &'static [&'static str],
+    /// Properties that may occur at most once.
+    optional: &'static [&'static str],
+    /// Pairs of properties that must not occur together.
+    exclusive: &'static [(&'static str, &'static str)],
+}
+
+const VCALENDAR: Rules = Rules {
+    required: &["PRODID", "VERSION"],
+    optional: &["CALSCALE", "METHOD"],
+    exclusive: &[],
+};
+
+const VEVENT: Rules = Rules {
+    required: &["UID", "DTSTAMP"],
+    optional: &["DTSTART", "CLASS", "CREATED", "DESCRIPTION", "GEO", "LAST-MODIFIED", "LOCATION",
+                "ORGANIZER", "PRIORITY", "SEQUENCE", "STATUS", "SUMMARY", "TRANSP", "URL",
+                "RECURRENCE-ID", "DTEND", "DURATION"],
+    exclusive: &[("DTEND", "DURATION")],
+};
+
+const VTODO: Rules = Rules {
+    required: &["UID", "DTSTAMP"],
+    optional: &["CLASS", "COMPLETED", "CREATED", "DESCRIPTION", "DTSTART", "GEO", "LAST-MODIFIED",
+                "LOCATION", "ORGANIZER", "PERCENT-COMPLETE", "PRIORITY", "RECURRENCE-ID",
+                "SEQUENCE", "STATUS", "SUMMARY", "URL", "DUE", "DURATION"],
+    exclusive: &[("DUE", "DURATION")],
+};
+
+const VJOURNAL: Rules = Rules {
+    required: &["UID", "DTSTAMP"],
+    optional: &["CLASS", "CREATED", "DTSTART", "LAST-MODIFIED", "ORGANIZER", "RECURRENCE-ID",
+                "SEQUENCE", "STATUS", "SUMMARY", "URL"],
+    exclusive: &[],
+};
+
+const VALARM: Rules = Rules {
+    required: &["ACTION", "TRIGGER"],
+    optional: &["DURATION", "REPEAT"],
+    exclusive: &[],
+};
+
+const VTIMEZONE: Rules = Rules {
+    required: &["TZID"],
+    optional: &["LAST-MODIFIED", "TZURL"],
+    exclusive: &[],
+};
+
+const OBSERVANCE: Rules = Rules {
+    required: &["DTSTART", "TZOFFSETTO", "TZOFFSETFROM"],
+    optional: &[],
+    exclusive: &[],
+};
+
+const VCARD: Rules = Rules {
+    required: &["VERSION"],
+    optional: &["N", "BDAY", "ANNIVERSARY", "GENDER", "PRODID", "REV", "UID"],
+    exclusive: &[],
+};
+
+fn rules_for(component: &str) -> Option<&'static Rules> {
+    match component {
+        "VCALENDAR" => Some(&VCALENDAR),
+        "VEVENT" => Some(&VEVENT),
+        "VTODO" => Some(&VTODO),
+        "VJOURNAL" => Some(&VJOURNAL),
+        "VALARM" => Some(&VALARM),
+        "VTIMEZONE" => Some(&VTIMEZONE),
+        "STANDARD" | "DAYLIGHT" => Some(&OBSERVANCE),
+        "VCARD" => Some(&VCARD),
+        _ => None,
+    }
+}
+
+/// The registered property names of iCalendar (RFC 5545, RFC 7986, RFC 9074) and vCard
+/// (RFC 2425, RFC 2426, RFC 6350 and its extensions), in uppercase.
+const KNOWN_PROPERTIES: &[&str] = &[
+    // iCalendar
+    "ACKNOWLEDGED", "ACTION", "ATTACH", "ATTENDEE", "CALSCALE", "CATEGORIES", "CLASS", "COLOR",
+    "COMMENT", "COMPLETED", "CONFERENCE", "CONTACT", "CREATED", "DESCRIPTION", "DTEND",
+    "DTSTAMP", "DTSTART", "DUE", "DURATION", "EXDATE", "EXRULE", "FREEBUSY", "GEO", "IMAGE",
+    "LAST-MODIFIED", "LOCATION", "METHOD", "NAME", "ORGANIZER", "PERCENT-COMPLETE", "PRIORITY",
+    "PRODID", "PROXIMITY", "RDATE", "RECURRENCE-ID", "REFRESH-INTERVAL", "RELATED-TO", "REPEAT",
+    "REQUEST-STATUS", "RESOURCES", "RRULE", "SEQUENCE", "SOURCE", "STATUS", "SUMMARY", "TRANSP",
+    "TRIGGER", "TZID", "TZNAME", "TZOFFSETFROM", "TZOFFSETTO", "TZURL", "UID", "URL", "VERSION",
+    // vCard
+    "ADR", "AGENT", "ANNIVERSARY", "BDAY", "BIRTHPLACE", "CALADRURI", "CALURI", "CLIENTPIDMAP",
+    "CONTACT-URI", "DEATHDATE", "DEATHPLACE", "EMAIL", "EXPERTISE", "FBURL", "FN", "GENDER",
+    "GRAMGENDER", "HOBBY", "IMPP", "INTEREST", "KEY", "KIND", "LABEL", "LANG", "LANGUAGE",
+    "LOGO", "MAILER", "MEMBER", "N", "NICKNAME", "NOTE", "ORG", "ORG-DIRECTORY", "PHOTO",
+    "PROFILE", "PRONOUNS", "RELATED", "REV", "ROLE", "SOCIALPROFILE", "SORT-STRING", "SOUND",
+    "TEL", "TITLE", "TZ", "XML",
+];
+
+/// Whether a property name is registered or an `X-` extension, ignoring case.
+pub fn is_known_property(name: &str) -> bool {
+    let extension = name.get(..2).is_some_and(|p| p.eq_ignore_ascii_case("X-"));
+    extension || KNOWN_PROPERTIES.iter().any(|p| p.eq_ignore_ascii_case(name))
+}
+
+/// Whether a property may occur at most once in a component, going by its (uppercase) name
+/// and, for a `VCARD`, its version: `FN` may occur several times only in vCard 4.0.
+/// Properties of unknown component types are assumed to allow several occurrences.
+pub fn is_single_valued(c: &Component, property: &str) -> bool {
+    if c.name == "VCARD" && property.eq_ignore_ascii_case("FN") {
+        return c.version() != Some(Version::V4_0);
+    }
+    rules_for(&c.name)
+        .map(|r| r.required.iter().chain(r.optional).any(|p| p.eq_ignore_ascii_case(property)))
+        .unwrap_or(false)
+}
+
+/// Check `c` and its subcomponents, appending every violation to `errors`.
+pub fn validate(c: &Component, errors: &mut Vec<ValidationError>) {
+    if let Some(rules) = rules_for(&c.name) {
+        check_rules(c, rules, errors);
+    }
+    if c.name == "VCARD" {
+        check_vcard(c, errors);
+    }
+
+    for sub in &c.subcomponents {
+        validate(sub, errors);
+    }
+}
+
+fn check_rules(c: &Component, rules: &Rules, errors: &mut Vec<ValidationError>) {
+    for &property in rules.required {
+        if c.get_all(property).is_empty() {
+            errors.push(ValidationError::MissingProperty { component: c.name.clone(), property });
+        }
+    }
+    for &property in rules.required.iter().chain(rules.optional) {
+        check_at_most_one(c, property, errors);
+    }
+    for &(first, second) in rules.exclusive {
+        if !c.get_all(first).is_empty() && !c.get_all(second).is_empty() {
+            errors.push(ValidationError::ConflictingProperties {
+                component: c.name.clone(),
+                first,
+                second,
+            });
+        }
+    }
+}
+
+fn check_at_most_one(c: &Component, property: &'static str, errors: &mut Vec<ValidationError>) {
+    let count = c.get_all(property).len();
+    if count > 1 {
+        errors.push(ValidationError::TooManyProperties { component: c.name.clone(), property, count });
+    }
+}
+
+/// The properties a vCard requires depend on its version: 2.1 requires `N`, 3.0 both `N` and
+/// `FN`, and 4.0 only `FN`. Before 4.0, `FN` may also occur only once.
+fn check_vcard(c: &Component, errors: &mut Vec<ValidationError>) {
+    let (needs_n, needs_fn) = match c.version() {
+        Some(Version::V2_1) => (true, false),
+        Some(Version::V3_0) => (true, true),
+        Some(Version::V4_0) => (false, true),
+        _ => (false, false),
+    };
+
+    for &(needed, property) in &[(needs_n, "N"), (needs_fn, "FN")] {
+        if needed && c.get_all(property).is_empty() {
+            errors.push(ValidationError::MissingProperty { component: c.name.clone(), property });
+        }
+    }
+    if is_single_valued(c, "FN") {
+        check_at_most_one(c, "FN", errors);
+    }
+}
@@ -0,0 +1,955 @@
+//! Typed representations of property values.
+
+#[cfg(feature = "timeconversions")] use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use std::fmt;
+use std::str::FromStr;
+
+use error::*;
+use property::Property;
+#[cfg(feature = "timeconversions")] use property::split_unescaped;
+
+#[cfg(feature = "timeconversions")] use util::{DATE_FMT, FLOATING_DATE_TIME_FMT};
+
+/// Create the error for a value that could not be read as `expected`.
+pub(crate) fn invalid(expected: &'static str, value: &str) -> VObjectError {
+    VObjectError::InvalidValue {
+        expected,
+        value: value.to_owned(),
+    }
+}
+
+/// Parse a `UTC-OFFSET` value such as `-0500` or `+013045` into seconds east of UTC. Hours
+/// above 23, minutes or seconds above 59, and `-0000` are rejected.
+pub(crate) fn parse_utc_offset(s: &str) -> Option<i32> {
+    let s = s.trim();
+    let sign = match s.as_bytes().first()? {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    let digits = &s[1..];
+    if !(digits.len() == 4 || digits.len() == 6) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let hours: i32 = digits[..2].parse().ok()?;
+    let minutes: i32 = digits[2..4].parse().ok()?;
+    let seconds: i32 = if digits.len() == 6 { digits[4..].parse().ok()? } else { 0 };
+    if hours > 23 || minutes > 59 || seconds > 59 {
+        return None;
+    }
+    let offset = hours * 3600 + minutes * 60 + seconds;
+    // RFC 5545 forbids a negative zero offset.
+    if sign < 0 && offset == 0 {
+        return None;
+    }
+    Some(sign * offset)
+}
+
+/// A `DURATION` value, such as `P1DT2H30M` or `-PT15M`, see RFC 5545, section 3.3.6.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Duration {
+    pub negative: bool,
+    pub weeks: u32,
+    pub days: u32,
+    pub hours: u32,
+    pub minutes: u32,
+    pub seconds: u32,
+}
+
+impl Duration {
+    /// Read the value of a property such as `DURATION` or `TRIGGER`.
+    pub fn from_property(prop: &Property) -> VObjectResult<Duration> {
+        match prop.value_type() {
+            ValueType::Duration | ValueType::Text | ValueType::Other(_) => prop.raw_value.parse(),
+            _ => Err(invalid("DURATION", &prop.raw_value)),
+        }
+    }
+
+    /// The total length in seconds, negative for negative durations.
+    pub fn as_seconds(&self) -> i64 {
+        let days = i64::from(self.weeks) * 7 + i64::from(self.days);
+        let seconds = ((days * 24 + i64::from(self.hours)) * 60 + i64::from(self.minutes)) * 60
+            + i64::from(self.seconds);
+        if self.negative { -seconds } else { seconds }
+    }
+}
+
+/// Consume a number followed by `unit` from the start of `s`, if it is there.
+fn take_unit(s: &mut &str, unit: char) -> Option<Option<u32>> {
+    let digits = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    if digits == 0 || !s[digits..].starts_with(unit) {
+        return Some(None);
+    }
+    let n = s[..digits].parse().ok()?;
+    *s = &s[digits + unit.len_utf8()..];
+    Some(Some(n))
+}
+
+impl FromStr for Duration {
+    type Err = VObjectError;
+
+    fn from_str(s: &str) -> VObjectResult<Duration> {
+        let invalid = || invalid("DURATION", s);
+
+        let (negative, rest) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let rest = rest.strip_prefix('P').ok_or_else(invalid)?;
+        let (mut date, time) = match rest.find('T') {
+            Some(i) => (&rest[..i], Some(&rest[i + 1..])),
+            None => (rest, None),
+        };
+
+        let mut rv = Duration { negative, ..Duration::default() };
+        match take_unit(&mut date, 'D').ok_or_else(invalid)? {
+            Some(days) => rv.days = days,
+            None if time.is_none() => {
+                rv.weeks = take_unit(&mut date, 'W').ok_or_else(invalid)?.ok_or_else(invalid)?;
+            },
+            None => (),
+        }
+        if !date.is_empty() {
+            return Err(invalid());
+        }
+
+        if let Some(mut time) = time {
+            let hours = take_unit(&mut time, 'H').ok_or_else(invalid)?;
+            let minutes = take_unit(&mut time, 'M').ok_or_else(invalid)?;
+            let seconds = take_unit(&mut time, 'S').ok_or_else(invalid)?;
+            if !time.is_empty() || (hours, minutes, seconds) == (None, None, None) {
+                return Err(invalid());
+            }
+            rv.hours = hours.unwrap_or(0);
+            rv.minutes = minutes.unwrap_or(0);
+            rv.seconds = seconds.unwrap_or(0);
+        }
+
+        Ok(rv)
+    }
+}
+
+impl fmt::Display for Duration {
+    /// Write the duration in the form RFC 5545 prescribes. Weeks are only written on their own,
+    /// and otherwise counted as days.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.negative {
+            f.write_str("-")?;
+        }
+        f.write_str("P")?;
+
+        let has_time = self.hours != 0 || self.minutes != 0 || self.seconds != 0;
+        if self.weeks != 0 && self.days == 0 && !has_time {
+            return write!(f, "{}W", self.weeks);
+        }
+
+        let days = u64::from(self.weeks) * 7 + u64::from(self.days);
+        if days != 0 {
+            write!(f, "{}D", days)?;
+        }
+        if has_time || days == 0 {
+            f.write_str("T")?;
+            if self.hours != 0 {
+                write!(f, "{}H", self.hours)?;
+            }
+            // The grammar only allows seconds after hours with minutes in between.
+            if self.minutes != 0 || (self.hours != 0 && self.seconds != 0) {
+                write!(f, "{}M", self.minutes)?;
+            }
+            if self.seconds != 0 || !has_time {
+                write!(f, "{}S", self.seconds)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The type of a property value, as given by the `VALUE` parameter or implied by the property.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum ValueType {
+    Binary,
+    Boolean,
+    CalAddress,
+    Date,
+    DateTime,
+    /// The vCard 4.0 type of `BDAY` and `ANNIVERSARY`: a date, a time or both.
+    DateAndOrTime,
+    Duration,
+    Float,
+    Integer,
+    LanguageTag,
+    Period,
+    Recur,
+    Text,
+    Time,
+    /// The vCard 4.0 type of `REV`, a UTC date-time.
+    Timestamp,
+    Uri,
+    UtcOffset,
+    /// A type this crate does not know, such as an `X-` type, in uppercase.
+    Other(String),
+}
+
+impl ValueType {
+    const ALL: [(ValueType, &'static str); 17] = [
+        (ValueType::Binary, "BINARY"),
+        (ValueType::Boolean, "BOOLEAN"),
+        (ValueType::CalAddress, "CAL-ADDRESS"),
+        (ValueType::Date, "DATE"),
+        (ValueType::DateTime, "DATE-TIME"),
+        (ValueType::DateAndOrTime, "DATE-AND-OR-TIME"),
+        (ValueType::Duration, "DURATION"),
+        (ValueType::Float, "FLOAT"),
+        (ValueType::Integer, "INTEGER"),
+        (ValueType::LanguageTag, "LANGUAGE-TAG"),
+        (ValueType::Period, "PERIOD"),
+        (ValueType::Recur, "RECUR"),
+        (ValueType::Text, "TEXT"),
+        (ValueType::Time, "TIME"),
+        (ValueType::Timestamp, "TIMESTAMP"),
+        (ValueType::Uri, "URI"),
+        (ValueType::UtcOffset, "UTC-OFFSET"),
+    ];
+
+    /// Look up a type by the name used in a `VALUE` parameter, ignoring case.
+    pub fn from_name(name: &str) -> ValueType {
+        ValueType::ALL.iter()
+            .find(|(_, n)| n.eq_ignore_ascii_case(name))
+            .map(|(t, _)| t.clone())
+            .unwrap_or_else(|| ValueType::Other(name.to_ascii_uppercase()))
+    }
+
+    /// The name of the type in a `VALUE` parameter, such as `DATE-TIME`.
+    pub fn as_str(&self) -> &str {
+        match *self {
+            ValueType::Other(ref name) => name,
+            ref t => ValueType::ALL.iter().find(|(u, _)| u == t).unwrap().1,
+        }
+    }
+
+    /// The default type of an iCalendar property, or `None` if the property is not known.
+    pub fn default_for_icalendar(name: &str) -> Option<ValueType> {
+        lookup_default(ICALENDAR_TYPES, name)
+    }
+
+    /// The default type of a vCard 4.0 property, or `None` if the property is not known.
+    pub fn default_for_vcard(name: &str) -> Option<ValueType> {
+        lookup_default(VCARD_TYPES, name)
+    }
+}
+
+fn lookup_default(types: &[(&str, ValueType)], name: &str) -> Option<ValueType> {
+    types.iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, t)| t.clone())
+}
+
+/// The default value types of the iCalendar properties of RFC 5545.
+const ICALENDAR_TYPES: &[(&str, ValueType)] = &[
+    ("ACTION", ValueType::Text), ("ATTACH", ValueType::Uri),
+    ("ATTENDEE", ValueType::CalAddress), ("CALSCALE", ValueType::Text),
+    ("CATEGORIES", ValueType::Text), ("CLASS", ValueType::Text), ("COMMENT", ValueType::Text),
+    ("COMPLETED", ValueType::DateTime), ("CONTACT", ValueType::Text),
+    ("CREATED", ValueType::DateTime), ("DESCRIPTION", ValueType::Text),
+    ("DTEND", ValueType::DateTime), ("DTSTAMP", ValueType::DateTime),
+    ("DTSTART", ValueType::DateTime), ("DUE", ValueType::DateTime),
+    ("DURATION", ValueType::Duration), ("EXDATE", ValueType::DateTime),
+    ("EXRULE", ValueType::Recur), ("FREEBUSY", ValueType::Period), ("GEO", ValueType::Float),
+    ("LAST-MODIFIED", ValueType::DateTime), ("LOCATION", ValueType::Text),
+    ("METHOD", ValueType::Text), ("ORGANIZER", ValueType::CalAddress),
+    ("PERCENT-COMPLETE", ValueType::Integer), ("PRIORITY", ValueType::Integer),
+    ("PRODID", ValueType::Text), ("RDATE", ValueType::DateTime),
+    ("RECURRENCE-ID", ValueType::DateTime), ("RELATED-TO", ValueType::Text),
+    ("REPEAT", ValueType::Integer), ("REQUEST-STATUS", ValueType::Text),
+    ("RESOURCES", ValueType::Text), ("RRULE", ValueType::Recur),
+    ("SEQUENCE", ValueType::Integer), ("STATUS", ValueType::Text), ("SUMMARY", ValueType::Text),
+    ("TRANSP", ValueType::Text), ("TRIGGER", ValueType::Duration), ("TZID", ValueType::Text),
+    ("TZNAME", ValueType::Text), ("TZOFFSETFROM", ValueType::UtcOffset),
+    ("TZOFFSETTO", ValueType::UtcOffset), ("TZURL", ValueType::Uri), ("UID", ValueType::Text),
+    ("URL", ValueType::Uri), ("VERSION", ValueType::Text),
+];
+
+/// The default value types of the vCard properties of RFC 6350.
+const VCARD_TYPES: &[(&str, ValueType)] = &[
+    ("ADR", ValueType::Text), ("ANNIVERSARY", ValueType::DateAndOrTime),
+    ("BDAY", ValueType::DateAndOrTime), ("CALADRURI", ValueType::Uri),
+    ("CALURI", ValueType::Uri), ("CATEGORIES", ValueType::Text),
+    ("CLIENTPIDMAP", ValueType::Text), ("EMAIL", ValueType::Text), ("FBURL", ValueType::Uri),
+    ("FN", ValueType::Text), ("GENDER", ValueType::Text), ("GEO", ValueType::Uri),
+    ("IMPP", ValueType::Uri), ("KEY", ValueType::Uri), ("KIND", ValueType::Text),
+    ("LANG", ValueType::LanguageTag), ("LOGO", ValueType::Uri), ("MEMBER", ValueType::Uri),
+    ("N", ValueType::Text), ("NICKNAME", ValueType::Text), ("NOTE", ValueType::Text),
+    ("ORG", ValueType::Text), ("PHOTO", ValueType::Uri), ("PRODID", ValueType::Text),
+    ("RELATED", ValueType::Uri), ("REV", ValueType::Timestamp), ("ROLE", ValueType::Text),
+    ("SOUND", ValueType::Uri), ("SOURCE", ValueType::Uri), ("TEL", ValueType::Text),
+    ("TITLE", ValueType::Text), ("TZ", ValueType::Text), ("UID", ValueType::Uri),
+    ("URL", ValueType::Uri), ("VERSION", ValueType::Text), ("XML", ValueType::Text),
+];
+
+/// The `FREQ` of a recurrence rule.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Frequency {
+    Secondly,
+    Minutely,
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl Frequency {
+    const ALL: [(Frequency, &'static str); 7] = [
+        (Frequency::Secondly, "SECONDLY"),
+        (Frequency::Minutely, "MINUTELY"),
+        (Frequency::Hourly, "HOURLY"),
+        (Frequency::Daily, "DAILY"),
+        (Frequency::Weekly, "WEEKLY"),
+        (Frequency::Monthly, "MONTHLY"),
+        (Frequency::Yearly, "YEARLY"),
+    ];
+
+    /// The name of the frequency in a recurrence rule, such as `WEEKLY`.
+    pub fn as_str(self) -> &'static str {
+        Frequency::ALL.iter().find(|&&(f, _)| f == self).unwrap().1
+    }
+}
+
+impl FromStr for Frequency {
+    type Err = VObjectError;
+
+    fn from_str(s: &str) -> VObjectResult<Frequency> {
+        Frequency::ALL.iter()
+            .find(|&&(_, name)| name.eq_ignore_ascii_case(s))
+            .map(|&(f, _)| f)
+            .ok_or_else(|| invalid("FREQ", s))
+    }
+}
+
+/// A day of the week, as used in `BYDAY` and `WKST`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    const ALL: [(Weekday, &'static str); 7] = [
+        (Weekday::Monday, "MO"),
+        (Weekday::Tuesday, "TU"),
+        (Weekday::Wednesday, "WE"),
+        (Weekday::Thursday, "TH"),
+        (Weekday::Friday, "FR"),
+        (Weekday::Saturday, "SA"),
+        (Weekday::Sunday, "SU"),
+    ];
+
+    /// The two-letter name of the day, such as `MO`.
+    pub fn as_str(self) -> &'static str {
+        Weekday::ALL.iter().find(|&&(d, _)| d == self).unwrap().1
+    }
+}
+
+impl FromStr for Weekday {
+    type Err = VObjectError;
+
+    fn from_str(s: &str) -> VObjectResult<Weekday> {
+        Weekday::ALL.iter()
+            .find(|&&(_, name)| name.eq_ignore_ascii_case(s))
+            .map(|&(d, _)| d)
+            .ok_or_else(|| invalid("weekday", s))
+    }
+}
+
+/// An entry of `BYDAY`, such as `MO`, `-1FR` or `+2TU`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct WeekdayNum {
+    /// Which occurrence of the day within the month or year is meant, counted from the end if
+    /// negative. `None` means every occurrence.
+    pub ordinal: Option<i32>,
+    pub weekday: Weekday,
+}
+
+impl FromStr for WeekdayNum {
+    type Err = VObjectError;
+
+    fn from_str(s: &str) -> VObjectResult<WeekdayNum> {
+        let split = s.len().saturating_sub(2);
+        if !s.is_char_boundary(split) {
+            return Err(invalid("weekday", s));
+        }
+        let (ordinal, weekday) = s.split_at(split);
+        let ordinal = match ordinal {
+            "" => None,
+            n => Some(n.parse().map_err(|_| invalid("weekday", s))?),
+        };
+
+        Ok(WeekdayNum { ordinal, weekday: weekday.parse()? })
+    }
+}
+
+impl fmt::Display for WeekdayNum {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(n) = self.ordinal {
+            write!(f, "{}", n)?;
+        }
+        f.write_str(self.weekday.as_str())
+    }
+}
+
+/// An `RRULE` recurrence rule, see RFC 5545, section 3.3.10.
+///
+/// The numbers in the `BYxxx` lists are kept as they were given, without checking their
+/// ranges.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct RRule {
+    pub freq: Frequency,
+    pub interval: Option<u32>,
+    pub count: Option<u32>,
+    /// The raw `UNTIL` value, a `DATE` or `DATE-TIME`.
+    pub until: Option<String>,
+    pub by_second: Vec<i32>,
+    pub by_minute: Vec<i32>,
+    pub by_hour: Vec<i32>,
+    pub by_day: Vec<WeekdayNum>,
+    pub by_month_day: Vec<i32>,
+    pub by_year_day: Vec<i32>,
+    pub by_week_no: Vec<i32>,
+    pub by_month: Vec<i32>,
+    pub by_set_pos: Vec<i32>,
+    pub week_start: Option<Weekday>,
+    /// Parts this type does not know about, such as `X-` extensions, in their original order.
+    pub other: Vec<(String, String)>,
+}
+
+impl RRule {
+    /// A rule with the given frequency and no other parts.
+    pub fn new(freq: Frequency) -> RRule {
+        RRule {
+            freq,
+            interval: None,
+            count: None,
+            until: None,
+            by_second: vec![],
+            by_minute: vec![],
+            by_hour: vec![],
+            by_day: vec![],
+            by_month_day: vec![],
+            by_year_day: vec![],
+            by_week_no: vec![],
+            by_month: vec![],
+            by_set_pos: vec![],
+            week_start: None,
+            other: vec![],
+        }
+    }
+
+    /// Read the value of an `RRULE` property.
+    pub fn from_property(prop: &Property) -> VObjectResult<RRule> {
+        match prop.value_type() {
+            ValueType::Recur | ValueType::Text | ValueType::Other(_) => prop.raw_value.parse(),
+            _ => Err(invalid("RECUR", &prop.raw_value)),
+        }
+    }
+}
+
+fn parse_list<T: FromStr>(name: &'static str, value: &str) -> VObjectResult<Vec<T>> {
+    value.split(',')
+        .map(|v| v.parse().map_err(|_| invalid(name, value)))
+        .collect()
+}
+
+impl FromStr for RRule {
+    type Err = VObjectError;
+
+    /// Parse a rule. `FREQ` is required, parts may come in any order, and `COUNT` and `UNTIL`
+    /// must not both be given.
+    fn from_str(s: &str) -> VObjectResult<RRule> {
+        let mut freq = None;
+        let mut rv = RRule::new(Frequency::Yearly);
+
+        for part in s.split(';').filter(|p| !p.is_empty()) {
+            let (name, value) = match part.find('=') {
+                Some(i) => (&part[..i], &part[i + 1..]),
+                None => return Err(invalid("RECUR", s)),
+            };
+            let number = || value.parse().map_err(|_| invalid("RECUR", s));
+
+            match &*name.to_ascii_uppercase() {
+                "FREQ" => freq = Some(value.parse()?),
+                "INTERVAL" => rv.interval = Some(number()?),
+                "COUNT" => rv.count = Some(number()?),
+                "UNTIL" => rv.until = Some(value.to_owned()),
+                "BYSECOND" => rv.by_second = parse_list("BYSECOND", value)?,
+                "BYMINUTE" => rv.by_minute = parse_list("BYMINUTE", value)?,
+                "BYHOUR" => rv.by_hour = parse_list("BYHOUR", value)?,
+                "BYDAY" => rv.by_day = value.split(',').map(str::parse).collect::<VObjectResult<_>>()?,
+                "BYMONTHDAY" => rv.by_month_day = parse_list("BYMONTHDAY", value)?,
+                "BYYEARDAY" => rv.by_year_day = parse_list("BYYEARDAY", value)?,
+                "BYWEEKNO" => rv.by_week_no = parse_list("BYWEEKNO", value)?,
+                "BYMONTH" => rv.by_month = parse_list("BYMONTH", value)?,
+                "BYSETPOS" => rv.by_set_pos = parse_list("BYSETPOS", value)?,
+                "WKST" => rv.week_start = Some(value.parse()?),
+                _ => rv.other.push((name.to_owned(), value.to_owned())),
+            }
+        }
+
+        if rv.count.is_some() && rv.until.is_some() {
+            return Err(invalid("RECUR", s));
+        }
+        rv.freq = freq.ok_or_else(|| invalid("RECUR", s))?;
+        Ok(rv)
+    }
+}
+
+impl fmt::Display for RRule {
+    /// Write the rule with `FREQ` first, followed by the other known parts in the order of
+    /// RFC 5545 and then the unknown ones.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn list<T: fmt::Display>(f: &mut fmt::Formatter, name: &str, values: &[T]) -> fmt::Result {
+            for (i, v) in values.iter().enumerate() {
+                if i == 0 {
+                    write!(f, ";{}={}", name, v)?;
+                } else {
+                    write!(f, ",{}", v)?;
+                }
+            }
+            Ok(())
+        }
+
+        write!(f, "FREQ={}", self.freq.as_str())?;
+        if let Some(ref until) = self.until {
+            write!(f, ";UNTIL={}", until)?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        if let Some(interval) = self.interval {
+            write!(f, ";INTERVAL={}", interval)?;
+        }
+        list(f, "BYSECOND", &self.by_second)?;
+        list(f, "BYMINUTE", &self.by_minute)?;
+        list(f, "BYHOUR", &self.by_hour)?;
+        list(f, "BYDAY", &self.by_day)?;
+        list(f, "BYMONTHDAY", &self.by_month_day)?;
+        list(f, "BYYEARDAY", &self.by_year_day)?;
+        list(f, "BYWEEKNO", &self.by_week_no)?;
+        list(f, "BYMONTH", &self.by_month)?;
+        list(f, "BYSETPOS", &self.by_set_pos)?;
+        if let Some(wkst) = self.week_start {
+            write!(f, ";WKST={}", wkst.as_str())?;
+        }
+        for (name, value) in &self.other {
+            write!(f, ";{}={}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// A `DATE` or `DATE-TIME` value, in one of the forms of RFC 5545, section 3.3.5.
+#[cfg(feature = "timeconversions")]
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum DateTimeValue {
+    /// A date without a time, such as `20240115`.
+    Date(NaiveDate),
+    /// A time in UTC, such as `20240115T130000Z`.
+    Utc(DateTime<Utc>),
+    /// A time that is the same in every time zone, such as `20240115T130000`.
+    Floating(NaiveDateTime),
+    /// A time in the time zone named by the `TZID` parameter.
+    Local { datetime: NaiveDateTime, tzid: String },
+}
+
+#[cfg(feature = "timeconversions")]
+impl DateTimeValue {
+    /// Read the value of a property such as `DTSTART`, taking its `VALUE` and `TZID`
+    /// parameters into account.
+    ///
+    /// Fails if the property has a value type other than a date or date-time, such as
+    /// `TRIGGER;VALUE=DURATION`. Properties of unknown or text type are read by the shape of
+    /// their value.
+    pub fn from_property(prop: &Property) -> VObjectResult<DateTimeValue> {
+        let is_date = DateTimeValue::is_date_property(prop)?;
+        DateTimeValue::parse(&prop.raw_value, is_date, prop.get_param("TZID"))
+    }
+
+    /// Read the values of a property such as `RDATE` or `EXDATE`, which may hold a
+    /// `,`-separated list. The `VALUE` and `TZID` parameters apply to every element.
+    ///
+    /// Fails like `from_property()`, so `RDATE;VALUE=PERIOD` has to be read with
+    /// `Period::list_from_property()` instead.
+    pub fn list_from_property(prop: &Property) -> VObjectResult<Vec<DateTimeValue>> {
+        let is_date = DateTimeValue::is_date_property(prop)?;
+        let tzid = prop.get_param("TZID");
+        split_unescaped(&prop.raw_value, ',')
+            .into_iter()
+            .map(|s| DateTimeValue::parse(s, is_date, tzid))
+            .collect()
+    }
+
+    fn is_date_property(prop: &Property) -> VObjectResult<bool> {
+        match prop.value_type() {
+            ValueType::Date => Ok(true),
+            ValueType::DateTime | ValueType::Timestamp | ValueType::Text | ValueType::Other(_) => {
+                Ok(false)
+            },
+            _ => Err(invalid("DATE-TIME", &prop.raw_value)),
+        }
+    }
+
+    /// Parse a date or date-time. A date is accepted if `is_date` is given or the value has no
+    /// time part. `tzid` applies to date-times without a `Z` suffix.
+    pub fn parse(s: &str, is_date: bool, tzid: Option<&str>) -> VObjectResult<DateTimeValue> {
+        let invalid = || invalid(if is_date { "DATE" } else { "DATE-TIME" }, s);
+
+        if is_date || !s.contains('T') {
+            return NaiveDate::parse_from_str(s, DATE_FMT)
+                .map(DateTimeValue::Date)
+                .map_err(|_| invalid());
+        }
+
+        let (datetime, utc) = match s.strip_suffix('Z') {
+            Some(rest) => (rest, true),
+            None => (s, false),
+        };
+        let datetime = NaiveDateTime::parse_from_str(datetime, FLOATING_DATE_TIME_FMT)
+            .map_err(|_| invalid())?;
+
+        Ok(match tzid {
+            _ if utc => DateTimeValue::Utc(Utc.from_utc_datetime(&datetime)),
+            Some(tzid) => DateTimeValue::Local { datetime, tzid: tzid.to_owned() },
+            None => DateTimeValue::Floating(datetime),
+        })
+    }
+
+    /// Add a duration. A date stays a date if the duration is a whole number of days and
+    /// becomes a floating date-time otherwise. Returns `None` if the result is out of range.
+    pub fn add_duration(&self, duration: &Duration) -> Option<DateTimeValue> {
+        let delta = chrono::Duration::seconds(duration.as_seconds());
+        Some(match *self {
+            DateTimeValue::Date(date) if duration.as_seconds() % 86400 == 0 => {
+                DateTimeValue::Date(date.checked_add_signed(delta)?)
+            },
+            DateTimeValue::Date(date) => {
+                DateTimeValue::Floating(date.and_hms_opt(0, 0, 0)?.checked_add_signed(delta)?)
+            },
+            DateTimeValue::Utc(datetime) => DateTimeValue::Utc(datetime.checked_add_signed(delta)?),
+            DateTimeValue::Floating(datetime) => {
+                DateTimeValue::Floating(datetime.checked_add_signed(delta)?)
+            },
+            DateTimeValue::Local { datetime, ref tzid } => DateTimeValue::Local {
+                datetime: datetime.checked_add_signed(delta)?,
+                tzid: tzid.clone(),
+            },
+        })
+    }
+}
+
+/// A `PERIOD` value, such as `20240101T090000Z/20240101T100000Z` or `20240101T090000Z/PT1H`,
+/// see RFC 5545, section 3.3.9.
+#[cfg(feature = "timeconversions")]
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct Period {
+    pub start: DateTimeValue,
+    pub end: PeriodEnd,
+}
+
+/// How a `Period` ends: at an explicit time, or after a duration.
+#[cfg(feature = "timeconversions")]
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum PeriodEnd {
+    End(DateTimeValue),
+    Duration(Duration),
+}
+
+#[cfg(feature = "timeconversions")]
+impl Period {
+    /// Read the periods of a property such as `FREEBUSY` or `RDATE;VALUE=PERIOD`, which may
+    /// hold a `,`-separated list. The `TZID` parameter applies to every time without a `Z`
+    /// suffix.
+    ///
+    /// Fails if the property has a value type other than a period, such as a plain `RDATE`.
+    pub fn list_from_property(prop: &Property) -> VObjectResult<Vec<Period>> {
+        match prop.value_type() {
+            ValueType::Period | ValueType::Text | ValueType::Other(_) => {},
+            _ => return Err(invalid("PERIOD", &prop.raw_value)),
+        }
+        let tzid = prop.get_param("TZID");
+        split_unescaped(&prop.raw_value, ',')
+            .into_iter()
+            .map(|s| Period::parse(s, tzid))
+            .collect()
+    }
+
+    /// Parse a single period. `tzid` applies to date-times without a `Z` suffix.
+    pub fn parse(s: &str, tzid: Option<&str>) -> VObjectResult<Period> {
+        let (start, end) = s.split_once('/').ok_or_else(|| invalid("PERIOD", s))?;
+        let date_time = |s: &str| match DateTimeValue::parse(s, false, tzid) {
+            Ok(DateTimeValue::Date(_)) | Err(_) => Err(invalid("PERIOD", s)),
+            Ok(value) => Ok(value),
+        };
+
+        let start = date_time(start)?;
+        let end = if end.starts_with(['P', '+', '-']) {
+            PeriodEnd::Duration(end.parse().map_err(|_| invalid("PERIOD", s))?)
+        } else {
+            PeriodEnd::End(date_time(end)?)
+        };
+        Ok(Period { start, end })
+    }
+
+    /// The time the period ends at, computing it from the duration if necessary. Returns `None`
+    /// if the result is out of range.
+    pub fn end_time(&self) -> Option<DateTimeValue> {
+        match self.end {
+            PeriodEnd::End(ref end) => Some(end.clone()),
+            PeriodEnd::Duration(ref duration) => self.start.add_duration(duration),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use component::parse_component;
+    use super::{Duration, Frequency, RRule, ValueType, Weekday, WeekdayNum};
+
+    fn duration(negative: bool, weeks: u32, days: u32, hours: u32, minutes: u32, seconds: u32) -> Duration {
+        Duration { negative, weeks, days, hours, minutes, seconds }
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        let parse = |s: &str| s.parse::<Duration>().unwrap();
+
+        // dur-week
+        assert_eq!(parse("P3W"), duration(false, 3, 0, 0, 0, 0));
+        // dur-date, with and without dur-time
+        assert_eq!(parse("P15D"), duration(false, 0, 15, 0, 0, 0));
+        assert_eq!(parse("P1DT2H30M"), duration(false, 0, 1, 2, 30, 0));
+        // dur-time with dur-hour, dur-minute and dur-second
+        assert_eq!(parse("PT1H"), duration(false, 0, 0, 1, 0, 0));
+        assert_eq!(parse("PT1H0M5S"), duration(false, 0, 0, 1, 0, 5));
+        assert_eq!(parse("PT15M"), duration(false, 0, 0, 0, 15, 0));
+        assert_eq!(parse("PT15M20S"), duration(false, 0, 0, 0, 15, 20));
+        assert_eq!(parse("PT45S"), duration(false, 0, 0, 0, 0, 45));
+        assert_eq!(parse("P7DT1H2M3S"), duration(false, 0, 7, 1, 2, 3));
+        // signs
+        assert_eq!(parse("-PT15M"), duration(true, 0, 0, 0, 15, 0));
+        assert_eq!(parse("+P1W"), duration(false, 1, 0, 0, 0, 0));
+
+        for invalid in &["", "P", "PT", "P1DT", "-", "1D", "P1H", "PT1D", "P1W2D", "P1WT1H", "PT1S2M",
+                         "P1D1D", "PD", "PTH", "P-1D", "P99999999999D", "P1DX"] {
+            assert!(invalid.parse::<Duration>().is_err(), "{:?} parsed", invalid);
+        }
+    }
+
+    #[test]
+    fn test_write_duration() {
+        for s in &["P3W", "P15D", "P1DT2H30M", "PT1H", "PT1H0M5S", "PT15M20S", "PT45S", "-PT15M", "PT0S"] {
+            assert_eq!(s.parse::<Duration>().unwrap().to_string(), *s);
+        }
+        assert_eq!(duration(false, 0, 0, 0, 0, 0).to_string(), "PT0S");
+        assert_eq!(duration(false, 1, 2, 0, 0, 0).to_string(), "P9D");
+        assert_eq!(duration(false, 1, 0, 0, 0, 1).to_string(), "P7DT1S");
+        assert_eq!("+P1W".parse::<Duration>().unwrap().to_string(), "P1W");
+    }
+
+    #[test]
+    fn test_duration_seconds() {
+        assert_eq!("P1W".parse::<Duration>().unwrap().as_seconds(), 7 * 86400);
+        assert_eq!("-P1DT1H1M1S".parse::<Duration>().unwrap().as_seconds(), -90061);
+    }
+
+    #[test]
+    fn test_parse_rrule() {
+        let rule = "FREQ=WEEKLY;BYDAY=MO,WE;COUNT=10".parse::<RRule>().unwrap();
+        assert_eq!(rule.freq, Frequency::Weekly);
+        assert_eq!(rule.count, Some(10));
+        assert_eq!(rule.by_day, vec![
+            WeekdayNum { ordinal: None, weekday: Weekday::Monday },
+            WeekdayNum { ordinal: None, weekday: Weekday::Wednesday },
+        ]);
+        assert_eq!(rule.to_string(), "FREQ=WEEKLY;COUNT=10;BYDAY=MO,WE");
+
+        let rule = "FREQ=MONTHLY;INTERVAL=2;BYDAY=-1FR,+2TU;BYMONTHDAY=-1,15;BYSETPOS=-1;\
+                    UNTIL=20241231T235959Z;WKST=SU;X-NAME=value;BYMONTH=1,6"
+            .parse::<RRule>().unwrap();
+        assert_eq!(rule.interval, Some(2));
+        assert_eq!(rule.until, Some("20241231T235959Z".to_owned()));
+        assert_eq!(rule.by_day, vec![
+            WeekdayNum { ordinal: Some(-1), weekday: Weekday::Friday },
+            WeekdayNum { ordinal: Some(2), weekday: Weekday::Tuesday },
+        ]);
+        assert_eq!(rule.by_month_day, vec![-1, 15]);
+        assert_eq!(rule.by_set_pos, vec![-1]);
+        assert_eq!(rule.by_month, vec![1, 6]);
+        assert_eq!(rule.week_start, Some(Weekday::Sunday));
+        assert_eq!(rule.other, vec![("X-NAME".to_owned(), "value".to_owned())]);
+        assert_eq!(rule.to_string(),
+                   "FREQ=MONTHLY;UNTIL=20241231T235959Z;INTERVAL=2;BYDAY=-1FR,2TU;\
+                    BYMONTHDAY=-1,15;BYMONTH=1,6;BYSETPOS=-1;WKST=SU;X-NAME=value");
+
+        let rule = "freq=daily;byhour=9,17;byminute=0".parse::<RRule>().unwrap();
+        assert_eq!(rule.to_string(), "FREQ=DAILY;BYMINUTE=0;BYHOUR=9,17");
+        assert_eq!(RRule::new(Frequency::Yearly).to_string(), "FREQ=YEARLY");
+
+        for invalid in &["", "COUNT=1", "FREQ=FORTNIGHTLY", "FREQ=DAILY;COUNT=x", "FREQ=DAILY;BYDAY=XX",
+                         "FREQ=DAILY;BYDAY=1", "FREQ=DAILY;BYDAY=ü", "FREQ=DAILY;BYMONTH=1,,2", "FREQ=DAILY;INTERVAL",
+                         "FREQ=DAILY;COUNT=1;UNTIL=20240101"] {
+            assert!(invalid.parse::<RRule>().is_err(), "{:?} parsed", invalid);
+        }
+    }
+
+    #[test]
+    fn test_value_type() {
+        let event = parse_component(
+            "BEGIN:VEVENT\n\
+            DTSTART;VALUE=date:20240101\n\
+            DTEND:20240102T000000\n\
+            TRIGGER;VALUE=DATE-TIME:20240101T090000Z\n\
+            RRULE;VALUE=TEXT:FREQ=DAILY\n\
+            GEO:48.2;16.4\n\
+            X-FOO;VALUE=X-BAR:baz\n\
+            X-QUX:quux\n\
+            END:VEVENT\n").unwrap();
+        let value_type = |name| event.get_only(name).unwrap().value_type();
+
+        assert_eq!(value_type("DTSTART"), ValueType::Date);
+        assert_eq!(value_type("DTEND"), ValueType::DateTime);
+        assert_eq!(value_type("TRIGGER"), ValueType::DateTime);
+        assert_eq!(value_type("RRULE"), ValueType::Text);
+        assert_eq!(value_type("GEO"), ValueType::Float);
+        assert_eq!(value_type("X-FOO"), ValueType::Other("X-BAR".to_owned()));
+        assert_eq!(value_type("X-FOO").as_str(), "X-BAR");
+        assert_eq!(value_type("X-QUX"), ValueType::Text);
+        assert_eq!(ValueType::DateAndOrTime.as_str(), "DATE-AND-OR-TIME");
+        assert_eq!(ValueType::default_for_vcard("bday"), Some(ValueType::DateAndOrTime));
+
+        assert!(event.get_only("TRIGGER").unwrap().value_as_duration().is_err());
+        assert!(event.get_only("RRULE").unwrap().value_as_rrule().is_ok());
+    }
+}
+
+#[cfg(all(test, feature = "timeconversions"))]
+mod datetime_tests {
+    use chrono::{NaiveDate, TimeZone, Utc};
+
+    use component::parse_component;
+    use error::VObjectError;
+    use super::{DateTimeValue, Period, PeriodEnd};
+
+    #[test]
+    fn test_datetime_values() {
+        let event = parse_component(
+            "BEGIN:VEVENT\n\
+            DTSTAMP:20240110T080910Z\n\
+            DTSTART;TZID=Europe/Berlin:20240115T130000\n\
+            DTEND:20240115T143000\n\
+            DUE;VALUE=DATE:20240116\n\
+            RDATE:20240117\n\
+            EXDATE;VALUE=DATE:20240117T130000\n\
+            RECURRENCE-ID:20241332T000000Z\n\
+            TRIGGER:-PT15M\n\
+            END:VEVENT\n").unwrap();
+        let value = |name| event.get_only(name).unwrap().value_as_datetime();
+        let time = |h, m| NaiveDate::from_ymd_opt(2024, 1, 15).unwrap().and_hms_opt(h, m, 0).unwrap();
+
+        assert_eq!(value("DTSTAMP").unwrap(),
+                   DateTimeValue::Utc(Utc.with_ymd_and_hms(2024, 1, 10, 8, 9, 10).unwrap()));
+        assert_eq!(value("DTSTART").unwrap(),
+                   DateTimeValue::Local { datetime: time(13, 0), tzid: "Europe/Berlin".to_owned() });
+        assert_eq!(value("DTEND").unwrap(), DateTimeValue::Floating(time(14, 30)));
+        assert_eq!(value("DUE").unwrap(), DateTimeValue::Date(NaiveDate::from_ymd_opt(2024, 1, 16).unwrap()));
+        assert_eq!(value("RDATE").unwrap(), DateTimeValue::Date(NaiveDate::from_ymd_opt(2024, 1, 17).unwrap()));
+
+        match value("EXDATE") {
+            Err(VObjectError::InvalidValue { expected: "DATE", .. }) => {},
+            other => panic!("unexpected {:?}", other),
+        }
+        match value("RECURRENCE-ID") {
+            Err(VObjectError::InvalidValue { expected: "DATE-TIME", ref value }) => {
+                assert_eq!(value, "20241332T000000Z")
+            },
+            other => panic!("unexpected {:?}", other),
+        }
+        match value("TRIGGER") {
+            Err(VObjectError::InvalidValue { expected: "DATE-TIME", .. }) => {},
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_date_lists() {
+        let event = parse_component(
+            "BEGIN:VEVENT\n\
+            RDATE;VALUE=DATE:20240101,20240215\n\
+            RDATE;TZID=Europe/Vienna:20240301T090000,20240302T100000\n\
+            EXDATE:20240101T090000Z,20240102\n\
+            EXDATE;VALUE=DATE:20240101,20240102T090000\n\
+            RDATE;VALUE=PERIOD:20240101T090000Z/PT1H\n\
+            END:VEVENT\n").unwrap();
+        let lists = |name| event.get_all(name).iter()
+            .map(|p| p.value_as_date_list())
+            .collect::<Vec<_>>();
+        let date = |m, d| DateTimeValue::Date(NaiveDate::from_ymd_opt(2024, m, d).unwrap());
+        let local = |d, h| DateTimeValue::Local {
+            datetime: NaiveDate::from_ymd_opt(2024, 3, d).unwrap().and_hms_opt(h, 0, 0).unwrap(),
+            tzid: "Europe/Vienna".to_owned(),
+        };
+
+        let rdates = lists("RDATE");
+        assert_eq!(rdates[0].as_ref().unwrap(), &vec![date(1, 1), date(2, 15)]);
+        assert_eq!(rdates[1].as_ref().unwrap(), &vec![local(1, 9), local(2, 10)]);
+        match rdates[2] {
+            Err(VObjectError::InvalidValue { expected: "DATE-TIME", .. }) => {},
+            ref other => panic!("unexpected {:?}", other),
+        }
+
+        let exdates = lists("EXDATE");
+        assert_eq!(exdates[0].as_ref().unwrap(),
+                   &vec![DateTimeValue::Utc(Utc.with_ymd_and_hms(2024, 1, 1, 9, 0, 0).unwrap()), date(1, 2)]);
+        match exdates[1] {
+            Err(VObjectError::InvalidValue { expected: "DATE", ref value }) => {
+                assert_eq!(value, "20240102T090000")
+            },
+            ref other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_periods() {
+        let prop = |s| parse_component(s).unwrap().get_only("FREEBUSY").unwrap().clone();
+        let utc = |d, h| DateTimeValue::Utc(Utc.with_ymd_and_hms(2024, 1, d, h, 0, 0).unwrap());
+
+        let periods = prop("BEGIN:VFREEBUSY\n\
+            FREEBUSY;FBTYPE=BUSY:20240101T090000Z/20240101T100000Z,20240102T090000Z/PT1H30M\n\
+            END:VFREEBUSY\n").value_as_periods().unwrap();
+        assert_eq!(periods, vec![
+            Period { start: utc(1, 9), end: PeriodEnd::End(utc(1, 10)) },
+            Period { start: utc(2, 9), end: PeriodEnd::Duration("PT1H30M".parse().unwrap()) },
+        ]);
+        assert_eq!(periods[1].end_time(),
+                   Some(DateTimeValue::Utc(Utc.with_ymd_and_hms(2024, 1, 2, 10, 30, 0).unwrap())));
+
+        let rdate = parse_component("BEGIN:VEVENT\n\
+            RDATE;VALUE=PERIOD;TZID=Europe/Vienna:20240101T090000/PT1H\n\
+            EXDATE:20240101T090000Z\n\
+            END:VEVENT\n").unwrap();
+        let local = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(9, 0, 0).unwrap();
+        assert_eq!(rdate.get_only("RDATE").unwrap().value_as_periods().unwrap()[0].start,
+                   DateTimeValue::Local { datetime: local, tzid: "Europe/Vienna".to_owned() });
+        assert!(rdate.get_only("EXDATE").unwrap().value_as_periods().is_err());
+
+        for invalid in &["20240101T090000Z", "20240101/20240102", "20240101T090000Z/P1X",
+                         "20240101T090000Z/"] {
+            assert!(Period::parse(invalid, None).is_err(), "{:?} parsed", invalid);
+        }
+    }
+}
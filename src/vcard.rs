@@ -1,8 +1,10 @@
 use std::ops::Deref;
 use std::collections::BTreeMap;
 
+use indexmap::IndexMap;
+
 use component::Component;
-use component::parse_component;
+use component::{self, parse_component, parse_component_with, ParseOptions};
 use property::Property;
 
 use std::result::Result as RResult;
@@ -22,11 +24,14 @@ impl Vcard {
     /// Returns an error if the parsed text is not a Vcard (that means that an error is returned
     /// also if this is a valid icalendar!)
     ///
+    /// A vCard 2.1 is parsed with `ParseOptions::vcard21`, so that bare parameters such as
+    /// `TEL;WORK:...` become `TYPE` values.
     pub fn build(s: &str) -> VObjectResult<Vcard> {
-        parse_component(s)
-            .and_then(|c| {
-                Self::from_component(c).map_err(|_| VObjectError::NotAVCard)
-            })
+        let mut c = parse_component(s)?;
+        if c.version() == Some(component::Version::V2_1) {
+            c = parse_component_with(s, &ParseOptions { vcard21: true, ..ParseOptions::default() })?;
+        }
+        Self::from_component(c).map_err(|_| VObjectError::NotAVCard)
     }
 
     /// Helper for `VcardBuilder::new()`
@@ -74,7 +79,32 @@ impl Vcard {
     make_getter_function_for_values!(url            , "URL"          , Url);
     make_getter_function_for_optional!(version      , "VERSION"      , Version);
 
-    fn set_properties(&mut self, props: BTreeMap<String, Vec<Property>>) {
+    /// The unescaped value of the first `FN` property.
+    pub fn formatted_name(&self) -> Option<String> {
+        self.0.get_all("FN").first().map(Property::value_as_string)
+    }
+
+    /// The components of the `N` property.
+    pub fn structured_name(&self) -> Option<StructuredName> {
+        self.0.get_only("N").map(StructuredName::from_property)
+    }
+
+    /// All `EMAIL` properties, with their parameters such as `TYPE`.
+    pub fn emails(&self) -> Vec<Property> {
+        self.0.get_all("EMAIL").to_vec()
+    }
+
+    /// All `TEL` properties, with their parameters such as `TYPE`.
+    pub fn telephones(&self) -> Vec<Property> {
+        self.0.get_all("TEL").to_vec()
+    }
+
+    /// Unwrap the underlying `VCARD` component.
+    pub fn into_component(self) -> Component {
+        self.0
+    }
+
+    fn set_properties(&mut self, props: IndexMap<String, Vec<Property>>) {
         self.0.props = props;
     }
 
@@ -96,7 +126,7 @@ impl Deref for Vcard {
 
 /// A builder for building a Vcard object.
 pub struct VcardBuilder {
-    properties: BTreeMap<String, Vec<Property>>
+    properties: IndexMap<String, Vec<Property>>
 }
 
 macro_rules! make_builder_fn {
@@ -104,6 +134,7 @@ macro_rules! make_builder_fn {
         fn $fnname:ident building $property_name:tt with_params,
         $mapfn:expr => $( $arg_name:ident : $arg_type:ty ),*
     ) => {
+        #[allow(clippy::too_many_arguments)]
         pub fn $fnname(mut self, params: $crate::param::Parameters, $( $arg_name : $arg_type ),*) -> Self {
             let raw_value = vec![ $( $arg_name ),* ]
                 .into_iter()
@@ -113,9 +144,11 @@ macro_rules! make_builder_fn {
 
             let prop = Property {
                 name: String::from($property_name),
-                params: params,
-                raw_value: raw_value,
-                prop_group: None
+                params,
+                raw_value,
+                prop_group: None,
+                param_breaks: BTreeMap::new(),
+                source_span: None,
             };
 
             self.properties.entry(String::from($property_name)).or_insert(vec![]).push(prop);
@@ -138,8 +171,10 @@ macro_rules! make_builder_fn {
             let prop = Property {
                 name: String::from($property_name),
                 params: BTreeMap::new(),
-                raw_value: raw_value,
-                prop_group: None
+                raw_value,
+                prop_group: None,
+                param_breaks: BTreeMap::new(),
+                source_span: None,
             };
             self.properties.entry(String::from($property_name)).or_insert(vec![]).push(prop);
             self
@@ -147,10 +182,16 @@ macro_rules! make_builder_fn {
     }
 }
 
+impl Default for VcardBuilder {
+    fn default() -> Self {
+        VcardBuilder::new()
+    }
+}
+
 impl VcardBuilder {
     pub fn new() -> Self {
         VcardBuilder {
-            properties: BTreeMap::new(),
+            properties: IndexMap::new(),
         }
     }
 
@@ -250,7 +291,7 @@ create_data_type!(Version);
 /// * If there is only one element after splitting, this is considered the lastname
 /// * If there are two elements, this is firstname and lastname
 /// * If there are more than two elements, firstname and lastname are the first and last elements
-/// respectively, all others are middlenames.
+///   respectively, all others are middlenames.
 ///
 impl Name {
 
@@ -286,6 +327,64 @@ impl Name {
 
 }
 
+/// The components of an `N` property, unescaped.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StructuredName {
+    pub family_name: String,
+    pub given_name: String,
+    pub additional_names: String,
+    pub honorific_prefixes: String,
+    pub honorific_suffixes: String,
+}
+
+impl StructuredName {
+    /// Read the components of an `N` property. Missing components are left empty, surplus ones
+    /// are ignored.
+    pub fn from_property(prop: &Property) -> StructuredName {
+        let mut parts = prop.value_as_components().into_iter();
+        let mut next = || parts.next().unwrap_or_default();
+
+        StructuredName {
+            family_name: next(),
+            given_name: next(),
+            additional_names: next(),
+            honorific_prefixes: next(),
+            honorific_suffixes: next(),
+        }
+    }
+}
+
+/// The components of an `ADR` property, unescaped.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StructuredAddress {
+    pub po_box: String,
+    pub extended_address: String,
+    pub street: String,
+    pub locality: String,
+    pub region: String,
+    pub postal_code: String,
+    pub country: String,
+}
+
+impl StructuredAddress {
+    /// Read the components of an `ADR` property. Missing components are left empty, surplus
+    /// ones are ignored.
+    pub fn from_property(prop: &Property) -> StructuredAddress {
+        let mut parts = prop.value_as_components().into_iter();
+        let mut next = || parts.next().unwrap_or_default();
+
+        StructuredAddress {
+            po_box: next(),
+            extended_address: next(),
+            street: next(),
+            locality: next(),
+            region: next(),
+            postal_code: next(),
+            country: next(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::Vcard;
@@ -316,6 +415,62 @@ mod test {
         assert_eq!(item.title()[0].raw() , "Oberleutnant");
     }
 
+    #[test]
+    fn test_structured_values() {
+        use super::{StructuredAddress, StructuredName};
+
+        let item = Vcard::build(
+            "BEGIN:VCARD\n\
+            N:Mustermann\\;Gabler;Erika;;Dr.\n\
+            ADR;HOME:;;Heidestrasse 17\\, 2. OG;Koeln;;51147;Deutschland\n\
+            END:VCARD\n").unwrap();
+
+        let name = StructuredName::from_property(item.get_only("N").unwrap());
+        assert_eq!(name, StructuredName {
+            family_name: String::from("Mustermann;Gabler"),
+            given_name: String::from("Erika"),
+            additional_names: String::new(),
+            honorific_prefixes: String::from("Dr."),
+            honorific_suffixes: String::new(),
+        });
+
+        let adr = StructuredAddress::from_property(item.get_only("ADR").unwrap());
+        assert_eq!(adr.po_box, "");
+        assert_eq!(adr.street, "Heidestrasse 17, 2. OG");
+        assert_eq!(adr.locality, "Koeln");
+        assert_eq!(adr.postal_code, "51147");
+        assert_eq!(adr.country, "Deutschland");
+    }
+
+    #[test]
+    fn test_vcard_accessors() {
+        let item = Vcard::build(
+            "BEGIN:VCARD\n\
+            VERSION:4.0\n\
+            UID:urn:uuid:4fbe8971-0bc3-424c-9c26-36c3e1eff6b1\n\
+            FN:Erika Mustermann\\, Dr.\n\
+            N:Mustermann;Erika;;Dr.;\n\
+            TEL;TYPE=work:+49 221 9999123\n\
+            TEL;TYPE=home:+49 221 1234567\n\
+            EMAIL;TYPE=work:erika@example.com\n\
+            END:VCARD\n").unwrap();
+
+        assert_eq!(item.formatted_name().unwrap(), "Erika Mustermann, Dr.");
+        assert_eq!(item.structured_name().unwrap().given_name, "Erika");
+        assert_eq!(item.uid().unwrap().raw(), "urn:uuid:4fbe8971-0bc3-424c-9c26-36c3e1eff6b1");
+
+        let tels = item.telephones();
+        assert_eq!(tels.len(), 2);
+        assert_eq!(tels[1].get_param("TYPE"), Some("home"));
+        assert_eq!(item.emails()[0].raw_value, "erika@example.com");
+
+        let empty = Vcard::default();
+        assert_eq!(empty.formatted_name(), None);
+        assert_eq!(empty.structured_name(), None);
+        assert!(empty.emails().is_empty());
+        assert_eq!(item.into_component().name, "VCARD");
+    }
+
     #[test]
     fn test_vcard_builder() {
         use component::write_component;
@@ -349,15 +504,15 @@ mod test {
 
         let expected =
             "BEGIN:VCARD\r\n\
-            ADR;TYPE=HOME:;;Heidestrasse 17;Koeln;;51147;Deutschland\r\n\
-            EMAIL:erika@mustermann.de\r\n\
-            FN:Erika Mustermann\r\n\
             N:;Mustermann;;Erika;\r\n\
+            FN:Erika Mustermann\r\n\
             ORG:Wikipedia\r\n\
-            REV:20140301T221110Z\r\n\
+            TITLE:Oberleutnant\r\n\
             TEL;TYPE=WORK:(0221) 9999123\r\n\
             TEL;TYPE=HOME:(0221) 1234567\r\n\
-            TITLE:Oberleutnant\r\n\
+            ADR;TYPE=HOME:;;Heidestrasse 17;Koeln;;51147;Deutschland\r\n\
+            EMAIL:erika@mustermann.de\r\n\
+            REV:20140301T221110Z\r\n\
             END:VCARD\r\n";
 
 
@@ -1,5 +1,20 @@
 extern crate vobject;
+#[cfg(feature = "serde")] extern crate serde_json;
 use vobject::parse_component;
+use vobject::parse_component_with;
+use vobject::parse_components;
+use vobject::parse_components_lenient;
+use vobject::parse_components_from_reader;
+use vobject::write_component;
+use vobject::write_component_to;
+use vobject::write_component_with;
+use vobject::WriteOptions;
+use vobject::LineEnding;
+use vobject::ParseOptions;
+use vobject::DecodeError;
+use vobject::ParseErrorKind;
+use vobject::Property;
+use vobject::Component;
 use std::borrow::ToOwned;
 
 macro_rules! s(
@@ -135,3 +150,2212 @@ fn test_property_groups() {
     assert_eq!(item.get_only("EMAIL").unwrap().prop_group, Some("foo".to_owned()));
 
 }
+
+#[test]
+fn test_property_order_preserved() {
+    let input = "BEGIN:VCARD\r\n\
+                 VERSION:3.0\r\n\
+                 N:Mustermann;Erika\r\n\
+                 TEL;TYPE=WORK:(0221) 9999123\r\n\
+                 FN:Erika Mustermann\r\n\
+                 TEL;TYPE=HOME:(0221) 1234567\r\n\
+                 EMAIL:erika@mustermann.de\r\n\
+                 END:VCARD\r\n";
+    let item = parse_component(input).unwrap();
+
+    let keys = item.props.keys().map(|k| &k[..]).collect::<Vec<_>>();
+    assert_eq!(keys, vec!["VERSION", "N", "TEL", "FN", "EMAIL"]);
+
+    let expected = "BEGIN:VCARD\r\n\
+                    VERSION:3.0\r\n\
+                    N:Mustermann;Erika\r\n\
+                    TEL;TYPE=WORK:(0221) 9999123\r\n\
+                    TEL;TYPE=HOME:(0221) 1234567\r\n\
+                    FN:Erika Mustermann\r\n\
+                    EMAIL:erika@mustermann.de\r\n\
+                    END:VCARD\r\n";
+    assert_eq!(write_component(&item), expected);
+}
+
+#[test]
+fn test_parse_error_kinds() {
+    let e = parse_component("BEGIN:VCARD\nFN:Erika Mustermann\n").unwrap_err();
+    assert_eq!(*e.kind(), ParseErrorKind::UnexpectedEof);
+
+    let e = parse_component("BEGIN:VEVENT\nSUMMARY:Meeting\nEND:VTODO\n").unwrap_err();
+    match *e.kind() {
+        ParseErrorKind::MismatchedEnd { ref expected, ref found } => {
+            assert_eq!(expected, "VEVENT");
+            assert_eq!(found, "VTODO");
+        },
+        ref kind => panic!("unexpected error: {:?}", kind),
+    }
+
+    let e = parse_component("FN:Erika Mustermann\n").unwrap_err();
+    assert_eq!(*e.kind(), ParseErrorKind::ExpectedBegin);
+
+    let e = parse_component("BEGIN:VCARD\nEND:VCARD\nBEGIN:VCARD\nEND:VCARD\n").unwrap_err();
+    assert_eq!(*e.kind(), ParseErrorKind::TrailingData(s!("BEGIN:VCARD\nEND:VCARD\n")));
+}
+
+#[test]
+fn test_parse_error_location() {
+    let e = parse_component(
+        "BEGIN:VCARD\r\n\
+        VERSION:3.0\r\n\
+        FN:Erika Mustermann\r\n\
+        EMAIL;TYPE=WORK\r\n\
+        END:VCARD\r\n").unwrap_err();
+    assert_eq!(*e.kind(), ParseErrorKind::UnexpectedChar(':', '\n'));
+    assert_eq!((e.line(), e.column()), (4, 16));
+    assert_eq!(e.to_string(), "expected :, found \n at line 4, column 16");
+
+    // Folded lines count as separate lines of the original input.
+    let e = parse_component(
+        "BEGIN:VCARD\n\
+        NOTE:This is a long\n \
+        note\n\
+        FN;Erika\n\
+        END:VCARD\n").unwrap_err();
+    assert_eq!((e.line(), e.column()), (4, 9));
+}
+
+#[test]
+fn test_param_values() {
+    let item = parse_component(
+            "BEGIN:VCARD\n\
+            TEL;TYPE=work,voice;PREF:(0221) 9999123\n\
+            EMAIL;TYPE=\"a,b\",c:foo@example.com\n\
+            END:VCARD\n").unwrap();
+
+    let tel = item.get_only("TEL").unwrap();
+    assert_eq!(tel.params["TYPE"], vec![s!("work"), s!("voice")]);
+    assert!(tel.params["PREF"].is_empty());
+    assert_eq!(tel.raw_value, s!("(0221) 9999123"));
+
+    let email = item.get_only("EMAIL").unwrap();
+    assert_eq!(email.params["TYPE"], vec![s!("a,b"), s!("c")]);
+    assert_eq!(email.raw_value, s!("foo@example.com"));
+}
+
+#[test]
+fn test_quoted_param_roundtrip() {
+    let item = parse_component(
+            "BEGIN:VCALENDAR\r\n\
+            ORGANIZER;CN=\"Doe; John\";DIR=\"ldap://example.com:6666\":mailto:jdoe@example.com\r\n\
+            ATTENDEE;MEMBER=\"a,b\",c;CN=Jane:mailto:jane@example.com\r\n\
+            END:VCALENDAR\r\n").unwrap();
+
+    let written = write_component(&item);
+    assert_eq!(written,
+               "BEGIN:VCALENDAR\r\n\
+               ORGANIZER;CN=\"Doe; John\";DIR=\"ldap://example.com:6666\":mailto:jdoe@example.\r\n \
+               com\r\n\
+               ATTENDEE;CN=Jane;MEMBER=\"a,b\",c:mailto:jane@example.com\r\n\
+               END:VCALENDAR\r\n");
+
+    let reparsed = parse_component(&written).unwrap();
+    let organizer = reparsed.get_only("ORGANIZER").unwrap();
+    assert_eq!(organizer.params["CN"], vec![s!("Doe; John")]);
+    assert_eq!(organizer.params["DIR"], vec![s!("ldap://example.com:6666")]);
+    assert_eq!(organizer.raw_value, s!("mailto:jdoe@example.com"));
+    let attendee = reparsed.get_only("ATTENDEE").unwrap();
+    assert_eq!(attendee.params["MEMBER"], vec![s!("a,b"), s!("c")]);
+}
+
+#[test]
+fn test_property_params() {
+    let item = parse_component(
+            "BEGIN:VCARD\n\
+            TEL;type=work,voice;PREF:(0221) 9999123\n\
+            END:VCARD\n").unwrap();
+    let mut tel = item.get_only("TEL").unwrap().clone();
+
+    assert_eq!(tel.get_param("TYPE"), Some("work"));
+    assert_eq!(tel.get_param("Type"), Some("work"));
+    assert_eq!(tel.get_param("pref"), Some(""));
+    assert_eq!(tel.get_param("VALUE"), None);
+
+    tel.set_param("TYPE", "home");
+    assert_eq!(tel.get_param("type"), Some("home"));
+    assert_eq!(tel.params.len(), 2);
+
+    assert_eq!(tel.remove_param("Pref"), Some(vec![]));
+    assert_eq!(tel.remove_param("PREF"), None);
+    assert_eq!(tel.params.keys().collect::<Vec<_>>(), vec!["TYPE"]);
+
+    let mut prop = Property::new("NOTE", "Hello");
+    prop.set_param("LANGUAGE", "de");
+    assert_eq!(prop.get_param("language"), Some("de"));
+}
+
+#[test]
+fn test_case_insensitive_names() {
+    let mut item = parse_component(
+            "BEGIN:vcard\n\
+            fn:Erika Mustermann\n\
+            Tel:(0221) 9999123\n\
+            TEL:(0221) 1234567\n\
+            END:VCARD\n").unwrap();
+
+    assert_eq!(item.name, s!("VCARD"));
+    assert_eq!(item.get_only("FN").unwrap().raw_value, s!("Erika Mustermann"));
+    assert_eq!(item.get_only("Fn").unwrap().name, s!("fn"));
+    assert_eq!(item.get_all("tel").len(), 2);
+
+    item.push(Property::new("email", "erika@mustermann.de"));
+    assert_eq!(item.get_all("EMAIL").len(), 1);
+    item.set(Property::new("Email", "erika@example.com"));
+    assert_eq!(item.get_only("eMail").unwrap().value_as_string(), s!("erika@example.com"));
+
+    assert_eq!(item.remove("tel").map(|v| v.len()), Some(2));
+    assert!(item.get_all("TEL").is_empty());
+    assert!(item.pop("fn").is_some());
+}
+
+#[test]
+fn test_remove_props() {
+    let mut item = parse_component(
+            "BEGIN:VCARD\n\
+            FN:Erika Mustermann\n\
+            PHOTO:http://example.com/1.jpg\n\
+            EMAIL:erika@mustermann.de\n\
+            EMAIL:erika@example.com\n\
+            PHOTO:http://example.com/2.jpg\n\
+            END:VCARD\n").unwrap();
+
+    let photos = item.remove("photo").unwrap();
+    assert_eq!(photos.len(), 2);
+    assert!(item.remove("PHOTO").is_none());
+
+    assert!(item.remove_single_prop("EMAIL", 2).is_none());
+    assert!(item.remove_single_prop("X-MISSING", 0).is_none());
+    let removed = item.remove_single_prop("email", 0).unwrap();
+    assert_eq!(removed.raw_value, s!("erika@mustermann.de"));
+    assert_eq!(item.get_only("EMAIL").unwrap().raw_value, s!("erika@example.com"));
+
+    item.remove_single_prop("EMAIL", 0).unwrap();
+    assert_eq!(item.props.keys().collect::<Vec<_>>(), vec!["FN"]);
+}
+
+#[test]
+fn test_set_props() {
+    let mut item = parse_component(
+            "BEGIN:VCARD\n\
+            UID:1234\n\
+            FN:Erika Mustermann\n\
+            FN:E. Mustermann\n\
+            END:VCARD\n").unwrap();
+
+    let previous = item.set(Property::new("FN", "Erika Musterfrau")).unwrap();
+    assert_eq!(previous.len(), 2);
+    assert_eq!(item.get_only("FN").unwrap().raw_value, s!("Erika Musterfrau"));
+
+    let previous = item.set_prop_value("uid", "5678; new").unwrap();
+    assert_eq!(previous[0].raw_value, s!("1234"));
+    assert_eq!(item.get_only("UID").unwrap().raw_value, s!("5678\\; new"));
+
+    assert!(item.set_prop_value("NOTE", "first").is_none());
+    assert_eq!(item.props.keys().collect::<Vec<_>>(), vec!["UID", "FN", "NOTE"]);
+}
+
+#[test]
+fn test_find_subcomponents() {
+    let item = parse_component(
+            "BEGIN:VCALENDAR\n\
+            BEGIN:VEVENT\n\
+            UID:1\n\
+            BEGIN:VALARM\n\
+            TRIGGER:-PT15M\n\
+            END:VALARM\n\
+            BEGIN:VALARM\n\
+            TRIGGER:-PT5M\n\
+            END:VALARM\n\
+            END:VEVENT\n\
+            BEGIN:VTODO\n\
+            UID:2\n\
+            BEGIN:VALARM\n\
+            TRIGGER:-PT1H\n\
+            END:VALARM\n\
+            END:VTODO\n\
+            BEGIN:VEVENT\n\
+            UID:3\n\
+            END:VEVENT\n\
+            END:VCALENDAR\n").unwrap();
+
+    let events = item.all_subcomponents("vevent");
+    assert_eq!(events.iter().map(|e| &e.get_only("UID").unwrap().raw_value[..]).collect::<Vec<_>>(),
+               vec!["1", "3"]);
+    assert!(item.all_subcomponents("VALARM").is_empty());
+
+    let alarms = item.find_subcomponents_recursive("VAlarm");
+    assert_eq!(alarms.iter().map(|a| &a.get_only("TRIGGER").unwrap().raw_value[..]).collect::<Vec<_>>(),
+               vec!["-PT15M", "-PT5M", "-PT1H"]);
+    assert!(item.find_subcomponents_recursive("VCALENDAR").is_empty());
+}
+
+#[test]
+fn test_add_subcomponents() {
+    let mut first = Component::new("VEVENT");
+    first.push(Property::new("UID", "1"));
+    let mut second = Component::new("VEVENT");
+    second.push(Property::new("UID", "2"));
+
+    let mut cal = Component::new("VCALENDAR")
+        .with_subcomponent(first)
+        .with_subcomponent(second);
+    cal.add_subcomponent(Component::new("VTODO"));
+
+    assert_eq!(cal.subcomponents.len(), 3);
+    assert_eq!(write_component(&cal),
+               "BEGIN:VCALENDAR\r\n\
+               BEGIN:VEVENT\r\n\
+               UID:1\r\n\
+               END:VEVENT\r\n\
+               BEGIN:VEVENT\r\n\
+               UID:2\r\n\
+               END:VEVENT\r\n\
+               BEGIN:VTODO\r\n\
+               END:VTODO\r\n\
+               END:VCALENDAR\r\n");
+}
+
+#[test]
+fn test_parse_components() {
+    let items = parse_components(
+            "BEGIN:VCARD\r\n\
+            FN:Erika Mustermann\r\n\
+            END:VCARD\r\n\
+            \r\n\
+            \r\n\
+            BEGIN:VCARD\r\n\
+            FN:Max Mustermann\r\n\
+            END:VCARD\r\n\
+            \n\
+            BEGIN:VCARD\n\
+            FN:Moritz Mustermann\n\
+            END:VCARD\n\n").unwrap();
+
+    let names = items.iter().map(|c| c.get_only("FN").unwrap().value_as_string()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["Erika Mustermann", "Max Mustermann", "Moritz Mustermann"]);
+
+    assert!(parse_components("").unwrap().is_empty());
+    assert!(parse_components("\r\n\n").unwrap().is_empty());
+
+    let e = parse_components(
+            "BEGIN:VCARD\n\
+            END:VCARD\n\
+            BEGIN:VCARD\n\
+            FN\n\
+            END:VCARD\n").unwrap_err();
+    assert_eq!(e.line(), 4);
+}
+
+#[test]
+fn test_parse_components_from_reader() {
+    use std::io::Cursor;
+    use vobject::ComponentReader;
+    use vobject::error::VObjectError;
+
+    let input = "BEGIN:VCALENDAR\r\n\
+                 BEGIN:VEVENT\r\n\
+                 SUMMARY:First\r\n\
+                 END:VEVENT\r\n\
+                 E\r\n \
+                 ND:VCALENDAR\r\n\
+                 \r\n\
+                 BEGIN:VCARD\n\
+                 FN:Erika\n  Mustermann\n\
+                 END:VCARD\n";
+
+    let items = parse_components_from_reader(Cursor::new(input)).unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].name, "VCALENDAR");
+    assert_eq!(items[0].subcomponents[0].get_only("SUMMARY").unwrap().raw_value, "First");
+    assert_eq!(items[1].get_only("FN").unwrap().raw_value, "Erika Mustermann");
+    assert!(parse_components_from_reader(Cursor::new("")).unwrap().is_empty());
+
+    // Errors are reported relative to the whole input, and later components are still read.
+    let mut reader = ComponentReader::new(Cursor::new(
+            "BEGIN:VCARD\n\
+            END:VCARD\n\
+            BEGIN:VCARD\n\
+            FN\n\
+            END:VCARD\n\
+            BEGIN:VCARD\n\
+            END:VCARD\n"));
+    assert!(reader.next().unwrap().is_ok());
+    match reader.next().unwrap() {
+        Err(VObjectError::Parse { source }) => assert_eq!(source.line(), 4),
+        other => panic!("unexpected {:?}", other),
+    }
+    assert!(reader.next().unwrap().is_ok());
+    assert!(reader.next().is_none());
+
+    match parse_components_from_reader(Cursor::new("BEGIN:VCARD\nFN:x\n")) {
+        Err(VObjectError::Parse { source }) => {
+            assert_eq!(*source.kind(), ParseErrorKind::UnexpectedEof)
+        },
+        other => panic!("unexpected {:?}", other),
+    }
+
+    match parse_components_from_reader(Cursor::new(&b"BEGIN:VCARD\nFN:\xff\nEND:VCARD\n"[..])) {
+        Err(VObjectError::Io { .. }) => {},
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn test_display() {
+    let input = "BEGIN:VCARD\r\n\
+                 FN:Erika Mustermann\r\n\
+                 TEL;TYPE=WORK:(0221) 9999123\r\n\
+                 END:VCARD\r\n";
+    let item = parse_component(input).unwrap();
+    assert_eq!(item.to_string(), input);
+    assert_eq!(format!("{}", item), write_component(&item));
+}
+
+#[test]
+fn test_from_str() {
+    let item = "BEGIN:VCARD\nFN:Erika Mustermann\nEND:VCARD\n".parse::<Component>().unwrap();
+    assert_eq!(item.get_only("FN").unwrap().raw_value, s!("Erika Mustermann"));
+
+    let e = "BEGIN:VCARD\nFN:Erika Mustermann\n".parse::<Component>().unwrap_err();
+    assert_eq!(*e.kind(), ParseErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn test_write_component_to() {
+    let mut c = Component::new("VCARD");
+    c.push(Property::new("FN", "Erika Mustermann"));
+    c.push(Property::new("NOTE", "a".repeat(80)));
+
+    let mut buf = Vec::new();
+    write_component_to(&mut buf, &c).unwrap();
+    assert_eq!(String::from_utf8(buf).unwrap(),
+               format!("BEGIN:VCARD\r\n\
+                        FN:Erika Mustermann\r\n\
+                        NOTE:{}\r\n {}\r\n\
+                        END:VCARD\r\n", "a".repeat(70), "a".repeat(10)));
+}
+
+#[test]
+fn test_write_fold_width() {
+    let mut c = Component::new("VCARD");
+    c.push(Property::new("NOTE", "Ünïcödé ".repeat(20)));
+
+    let unfolded = write_component_with(&c, &WriteOptions { fold_width: None, ..WriteOptions::default() });
+    assert_eq!(unfolded, format!("BEGIN:VCARD\r\nNOTE:{}\r\nEND:VCARD\r\n", "Ünïcödé ".repeat(20)));
+
+    for width in &[0, 1, 2, 3, 10, 40, 75] {
+        let written = write_component_with(&c, &WriteOptions { fold_width: Some(*width), ..WriteOptions::default() });
+        for line in written.split("\r\n").filter(|l| !l.contains("VCARD")) {
+            assert!(line.len() <= *width || line.chars().count() <= 2, "{:?}", line);
+        }
+        let reparsed = parse_component(&written).unwrap();
+        assert_eq!(reparsed.get_only("NOTE").unwrap().value_as_string(), "Ünïcödé ".repeat(20));
+    }
+}
+
+#[test]
+fn test_write_lf() {
+    let mut c = Component::new("VCARD");
+    c.push(Property::new("FN", "Erika Mustermann"));
+    c.push(Property::new("NOTE", "a".repeat(80)));
+    c.add_subcomponent(Component::new("X-CHILD"));
+
+    let options = WriteOptions { line_ending: LineEnding::Lf, ..WriteOptions::default() };
+    let written = write_component_with(&c, &options);
+    assert_eq!(written,
+               format!("BEGIN:VCARD\n\
+                        FN:Erika Mustermann\n\
+                        NOTE:{}\n {}\n\
+                        BEGIN:X-CHILD\n\
+                        END:X-CHILD\n\
+                        END:VCARD\n", "a".repeat(70), "a".repeat(10)));
+
+    let reparsed = parse_component(&written).unwrap();
+    assert_eq!(reparsed.get_only("NOTE").unwrap().raw_value, "a".repeat(80));
+    assert_eq!(reparsed.subcomponents.len(), 1);
+    assert_eq!(write_component(&reparsed), write_component(&c));
+}
+
+#[test]
+fn test_write_params_sorted() {
+    let input = "BEGIN:VCARD\r\n\
+                 TEL;VALUE=uri;TYPE=work;PREF=1:tel:+49-221-9999123\r\n\
+                 END:VCARD\r\n";
+    let expected = "BEGIN:VCARD\r\n\
+                    TEL;PREF=1;TYPE=work;VALUE=uri:tel:+49-221-9999123\r\n\
+                    END:VCARD\r\n";
+    assert_eq!(write_component(&parse_component(input).unwrap()), expected);
+
+    let mut prop = Property::new("TEL", "tel:+49-221-9999123");
+    prop.set_param("VALUE", "uri");
+    prop.set_param("PREF", "1");
+    prop.set_param("TYPE", "work");
+    let mut c = Component::new("VCARD");
+    c.push(prop);
+    assert_eq!(write_component(&c), expected);
+}
+
+#[test]
+fn test_parse_components_lenient() {
+    let (items, errors) = parse_components_lenient(
+            "BEGIN:VCARD\n\
+            FN:Erika Mustermann\n\
+            ;garbage\n\
+            EMAIL:erika@example.com\n\
+            END:VCARD\n\
+            BEGIN:VCARD\n\
+            FN:Max Mustermann\n\
+            BEGIN:X-CHILD\n\
+            NOTE\n\
+            END:VCARD\n\
+            END:X-NOWHERE\n\
+            BEGIN:VCARD\n\
+            FN:Moritz Mustermann\n");
+
+    let names = items.iter().map(|c| c.get_only("FN").unwrap().raw_value.clone()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["Erika Mustermann", "Max Mustermann", "Moritz Mustermann"]);
+    assert_eq!(items[0].get_only("EMAIL").unwrap().raw_value, "erika@example.com");
+    assert_eq!(items[1].subcomponents.len(), 1);
+
+    let kinds = errors.iter().map(|e| (e.line(), e.kind().clone())).collect::<Vec<_>>();
+    assert_eq!(kinds, vec![
+        (3, ParseErrorKind::NoPropertyName),
+        (9, ParseErrorKind::UnexpectedChar(':', '\n')),
+        (10, ParseErrorKind::MismatchedEnd { expected: s!("X-CHILD"), found: s!("VCARD") }),
+        (11, ParseErrorKind::ExpectedBegin),
+        (14, ParseErrorKind::UnexpectedEof),
+    ]);
+
+    let (items, errors) = parse_components_lenient("BEGIN:VCARD\nEND:VCARD\n");
+    assert_eq!(items.len(), 1);
+    assert!(errors.is_empty());
+
+    // An END that matches no open component is reported once, not again for every enclosing
+    // component and the top level.
+    let (items, errors) = parse_components_lenient(
+            "BEGIN:VCALENDAR\n\
+            BEGIN:VEVENT\n\
+            END:X-STRAY\n\
+            END:VCALENDAR\n\
+            END:VCARD\n\
+            BEGIN:VCARD\n\
+            FN:Erika Mustermann\n\
+            END:VCARD\n");
+    assert_eq!(items.len(), 2);
+    let kinds = errors.iter().map(|e| (e.line(), e.kind().clone())).collect::<Vec<_>>();
+    assert_eq!(kinds, vec![
+        (3, ParseErrorKind::MismatchedEnd { expected: s!("VEVENT"), found: s!("X-STRAY") }),
+        (4, ParseErrorKind::ExpectedBegin),
+        (5, ParseErrorKind::ExpectedBegin),
+    ]);
+}
+
+#[test]
+fn test_bom() {
+    let input = "\u{feff}BEGIN:VCARD\r\nFN:Erika Mustermann\r\nEND:VCARD\r\n";
+    let item = parse_component(input).unwrap();
+    assert_eq!(item.name, "VCARD");
+    assert_eq!(item.get_only("FN").unwrap().raw_value, "Erika Mustermann");
+
+    assert_eq!(parse_components(input).unwrap().len(), 1);
+    assert_eq!(parse_components_from_reader(input.as_bytes()).unwrap().len(), 1);
+
+    // Only a leading BOM is dropped.
+    let item = parse_component("\u{feff}BEGIN:VCARD\r\nNOTE:\u{feff}x\r\nEND:VCARD\r\n").unwrap();
+    assert_eq!(item.get_only("NOTE").unwrap().raw_value, "\u{feff}x");
+    assert!(parse_component("\u{feff}\u{feff}BEGIN:VCARD\r\nEND:VCARD\r\n").is_err());
+}
+
+#[test]
+fn test_get_values() {
+    let item = parse_component(
+            "BEGIN:VCARD\n\
+            FN:Erika\\, Mustermann\n\
+            EMAIL:erika@example.com\n\
+            EMAIL:mustermann@example.com\n\
+            END:VCARD\n").unwrap();
+
+    assert_eq!(item.get_value("fn"), Some(s!("Erika, Mustermann")));
+    assert_eq!(item.get_value("EMAIL"), None);
+    assert_eq!(item.get_value("NOTE"), None);
+    assert_eq!(item.get_all_values("EMAIL"), vec!["erika@example.com", "mustermann@example.com"]);
+    assert_eq!(item.get_all_values("FN"), vec!["Erika, Mustermann"]);
+    assert!(item.get_all_values("NOTE").is_empty());
+}
+
+#[test]
+fn test_list_values() {
+    let item = parse_component(
+            "BEGIN:VCARD\n\
+            CATEGORIES:Friends,Work\\, Cologne office,Choir\\;Tenor\n\
+            NICKNAME:Eri\n\
+            END:VCARD\n").unwrap();
+
+    let categories = item.get_only("CATEGORIES").unwrap();
+    assert_eq!(categories.value_as_list(), vec!["Friends", "Work, Cologne office", "Choir;Tenor"]);
+    assert_eq!(item.get_only("NICKNAME").unwrap().value_as_list(), vec!["Eri"]);
+
+    let mut prop = Property::new("CATEGORIES", "");
+    prop.set_list(&["Friends", "Work, Cologne office", "C:\\"]);
+    assert_eq!(prop.raw_value, "Friends,Work\\, Cologne office,C:\\\\");
+    assert_eq!(prop.value_as_list(), vec!["Friends", "Work, Cologne office", "C:\\"]);
+}
+
+#[test]
+fn test_decode_base64_value() {
+    let item = parse_component(
+            "BEGIN:VCARD\n\
+            PHOTO;ENCODING=b;TYPE=JPEG:/9j/4AAQ\n\
+            KEY;ENCODING=BASE64:\n  SGVsbG8=\n\
+            LOGO;ENCODING=b:not base64!\n\
+            NOTE:Hello\\, World\n\
+            END:VCARD\n").unwrap();
+
+    assert_eq!(item.get_only("PHOTO").unwrap().decode_value().unwrap(), [0xff, 0xd8, 0xff, 0xe0, 0x00, 0x10]);
+    assert_eq!(item.get_only("KEY").unwrap().decode_value().unwrap(), b"Hello");
+    assert_eq!(item.get_only("LOGO").unwrap().decode_value(), Err(DecodeError::InvalidBase64));
+    assert_eq!(item.get_only("NOTE").unwrap().decode_value().unwrap(), b"Hello, World");
+}
+
+#[test]
+fn test_decode_quoted_printable_value() {
+    let item = parse_component(
+            "BEGIN:VCARD\r\n\
+            VERSION:2.1\r\n\
+            NOTE;ENCODING=QUOTED-PRINTABLE:Erste Zeile=0D=0A=\r\n\
+            zweite Zeile in K=C3=B6ln\r\n\
+            ADR;HOME;QUOTED-PRINTABLE:;;Heidestra=C3=9Fe 17;K=\r\n\
+            =C3=B6ln\r\n\
+            FN:Erika=\r\n\
+            END:VCARD\r\n").unwrap();
+
+    let note = item.get_only("NOTE").unwrap();
+    assert_eq!(note.raw_value, "Erste Zeile=0D=0Azweite Zeile in K=C3=B6ln");
+    assert_eq!(note.decode_value().unwrap(), "Erste Zeile\r\nzweite Zeile in Köln".as_bytes());
+
+    let adr = item.get_only("ADR").unwrap();
+    assert_eq!(adr.decode_value().unwrap(), ";;Heidestraße 17;Köln".as_bytes());
+
+    // Without a quoted-printable encoding, a trailing `=` is just part of the value.
+    assert_eq!(item.get_only("FN").unwrap().raw_value, "Erika=");
+}
+
+#[test]
+fn test_value_decoded_as_string() {
+    let item = parse_component(
+            "BEGIN:VCARD\r\n\
+            VERSION:2.1\r\n\
+            N;CHARSET=ISO-8859-1;ENCODING=QUOTED-PRINTABLE:M=FCller;J=FCrgen\r\n\
+            NOTE;CHARSET=WINDOWS-1252;QUOTED-PRINTABLE:=93Hallo=94 =80\r\n\
+            TITLE;ENCODING=QUOTED-PRINTABLE:Gesch=C3=A4ftsf=C3=BChrer\r\n\
+            ROLE;ENCODING=QUOTED-PRINTABLE:Gesch=E4ftsf=FChrer\r\n\
+            ORG;CHARSET=KOI8-R:Wikipedia\r\n\
+            FN;CHARSET=ISO-8859-1:Jürgen Müller\r\n\
+            END:VCARD\r\n").unwrap();
+
+    let value = |name| item.get_only(name).unwrap().value_decoded_as_string();
+    assert_eq!(value("N").unwrap(), "Müller;Jürgen");
+    assert_eq!(value("NOTE").unwrap(), "\u{201C}Hallo\u{201D} €");
+    assert_eq!(value("TITLE").unwrap(), "Geschäftsführer");
+    assert_eq!(value("ROLE"), Err(DecodeError::InvalidUtf8));
+    assert_eq!(value("ORG"), Err(DecodeError::UnknownCharset(s!("KOI8-R"))));
+    assert_eq!(value("FN").unwrap(), "Jürgen Müller");
+}
+
+#[test]
+fn test_icalendar_wrapper() {
+    use vobject::ICalendar;
+
+    let cal = ICalendar::build(
+            "BEGIN:VCALENDAR\n\
+            VERSION:2.0\n\
+            PRODID:-//Example//Calendar//EN\n\
+            BEGIN:VTODO\n\
+            SUMMARY:First\n\
+            END:VTODO\n\
+            BEGIN:VEVENT\n\
+            SUMMARY:Meeting\n\
+            END:VEVENT\n\
+            BEGIN:VJOURNAL\n\
+            SUMMARY:Notes\n\
+            END:VJOURNAL\n\
+            BEGIN:VTODO\n\
+            SUMMARY:Second\n\
+            END:VTODO\n\
+            END:VCALENDAR\n").unwrap();
+
+    assert_eq!(cal.version().unwrap().raw(), "2.0");
+    assert_eq!(cal.prodid().unwrap().raw(), "-//Example//Calendar//EN");
+    let todos = cal.todos().map(|c| c.get_value("SUMMARY").unwrap()).collect::<Vec<_>>();
+    assert_eq!(todos, vec!["First", "Second"]);
+    assert_eq!(cal.journals().count(), 1);
+    assert_eq!(cal.events().filter_map(Result::ok).count(), 1);
+
+    // The underlying component is still available.
+    assert_eq!(cal.subcomponents.len(), 4);
+    assert_eq!(cal.into_component().name, "VCALENDAR");
+
+    assert!(ICalendar::build("BEGIN:VCARD\nEND:VCARD\n").is_err());
+    assert!(ICalendar::from_component(Component::new("VEVENT")).is_err());
+}
+
+#[test]
+fn test_component_builder() {
+    let mut params = vobject::param::Parameters::new();
+    params.insert(s!("ROLE"), vec![s!("CHAIR")]);
+    params.insert(s!("CN"), vec![s!("Doe, John")]);
+
+    let event = Component::builder("vevent")
+        .prop("UID", "1234@example.com")
+        .prop("SUMMARY", "Meeting; bring notes")
+        .prop_with_params("ATTENDEE", "mailto:jdoe@example.com", params)
+        .prop("ATTENDEE", "mailto:erika@example.com")
+        .subcomponent(Component::builder("VALARM").prop("TRIGGER", "-PT15M").build())
+        .build();
+
+    assert_eq!(write_component(&event),
+               "BEGIN:VEVENT\r\n\
+               UID:1234@example.com\r\n\
+               SUMMARY:Meeting\\; bring notes\r\n\
+               ATTENDEE;CN=\"Doe, John\";ROLE=CHAIR:mailto:jdoe@example.com\r\n\
+               ATTENDEE:mailto:erika@example.com\r\n\
+               BEGIN:VALARM\r\n\
+               TRIGGER:-PT15M\r\n\
+               END:VALARM\r\n\
+               END:VEVENT\r\n");
+}
+
+#[test]
+fn test_validate() {
+    use vobject::ValidationError;
+
+    let cal = parse_component(
+            "BEGIN:VCALENDAR\n\
+            VERSION:2.0\n\
+            PRODID:-//Example//Calendar//EN\n\
+            BEGIN:VEVENT\n\
+            UID:1@example.com\n\
+            DTSTAMP:20240101T000000Z\n\
+            BEGIN:VALARM\n\
+            ACTION:DISPLAY\n\
+            TRIGGER:-PT15M\n\
+            END:VALARM\n\
+            END:VEVENT\n\
+            END:VCALENDAR\n").unwrap();
+    assert_eq!(cal.validate(), Ok(()));
+
+    let cal = parse_component(
+            "BEGIN:VCALENDAR\n\
+            VERSION:2.0\n\
+            BEGIN:VEVENT\n\
+            DTSTAMP:20240101T000000Z\n\
+            SUMMARY:One\n\
+            SUMMARY:Two\n\
+            DTEND:20240101T010000Z\n\
+            DURATION:PT1H\n\
+            END:VEVENT\n\
+            BEGIN:X-CUSTOM\n\
+            END:X-CUSTOM\n\
+            END:VCALENDAR\n").unwrap();
+    assert_eq!(cal.validate(), Err(vec![
+        ValidationError::MissingProperty { component: s!("VCALENDAR"), property: "PRODID" },
+        ValidationError::MissingProperty { component: s!("VEVENT"), property: "UID" },
+        ValidationError::TooManyProperties { component: s!("VEVENT"), property: "SUMMARY", count: 2 },
+        ValidationError::ConflictingProperties { component: s!("VEVENT"), first: "DTEND", second: "DURATION" },
+    ]));
+
+    let card = parse_component("BEGIN:VCARD\nVERSION:3.0\nFN:Erika\nEND:VCARD\n").unwrap();
+    assert_eq!(card.validate(), Err(vec![
+        ValidationError::MissingProperty { component: s!("VCARD"), property: "N" },
+    ]));
+    let card = parse_component("BEGIN:VCARD\nVERSION:4.0\nFN:Erika\nEND:VCARD\n").unwrap();
+    assert_eq!(card.validate(), Ok(()));
+    let card = parse_component("BEGIN:VCARD\nFN:Erika\nEND:VCARD\n").unwrap();
+    assert_eq!(card.validate(), Err(vec![
+        ValidationError::MissingProperty { component: s!("VCARD"), property: "VERSION" },
+    ]));
+
+    assert_eq!(Component::new("X-UNKNOWN").validate(), Ok(()));
+}
+
+#[test]
+fn test_component_eq() {
+    let input = "BEGIN:VCARD\r\n\
+                 VERSION:3.0\r\n\
+                 FN:Erika Mustermann\r\n\
+                 N:Mustermann;Erika\r\n\
+                 TEL;TYPE=work,voice:(0221) 9999123\r\n\
+                 TEL;TYPE=home:(0221) 1234567\r\n\
+                 item1.EMAIL;PREF:erika@example.com\r\n\
+                 NOTE:A long note that has to be folded when it is written out again\\, \
+                 since it does not fit on one line\r\n\
+                 BEGIN:X-CHILD\r\n\
+                 END:X-CHILD\r\n\
+                 END:VCARD\r\n";
+    let item = parse_component(input).unwrap();
+    let reparsed = parse_component(&write_component(&item)).unwrap();
+    assert_eq!(item, reparsed);
+
+    // Differently named properties may come in any order.
+    let mut reordered = Component::new("VCARD");
+    for name in &["NOTE", "item1.EMAIL", "TEL", "N", "FN", "VERSION"] {
+        for prop in item.get_all(name.trim_start_matches("item1.")) {
+            reordered.push(prop.clone());
+        }
+    }
+    reordered.subcomponents = item.subcomponents.clone();
+    assert_eq!(item, reordered);
+
+    // But the order of same-named properties matters, as do values and parameters.
+    let mut swapped = item.clone();
+    swapped.props.get_mut("TEL").unwrap().reverse();
+    assert!(item != swapped);
+
+    let mut changed = item.clone();
+    changed.props.get_mut("TEL").unwrap()[0].set_param("TYPE", "cell");
+    assert!(item != changed);
+
+    assert!(item != Component::new("VCARD"));
+}
+
+#[test]
+fn test_iter_props() {
+    let mut item = parse_component(
+            "BEGIN:VCARD\n\
+            FN:Erika Mustermann\n\
+            tel:221 9999123\n\
+            EMAIL:erika@example.com\n\
+            TEL:221 1234567\n\
+            BEGIN:X-CHILD\n\
+            NOTE:not included\n\
+            END:X-CHILD\n\
+            END:VCARD\n").unwrap();
+
+    let props = item.iter_props().map(|(k, p)| (k, &p.raw_value[..])).collect::<Vec<_>>();
+    assert_eq!(props, vec![
+        ("FN", "Erika Mustermann"),
+        ("TEL", "221 9999123"),
+        ("TEL", "221 1234567"),
+        ("EMAIL", "erika@example.com"),
+    ]);
+
+    for (key, prop) in item.iter_props_mut() {
+        if key == "TEL" {
+            prop.raw_value = format!("+49 {}", prop.raw_value);
+        }
+    }
+    assert_eq!(item.get_all_values("TEL"), vec!["+49 221 9999123", "+49 221 1234567"]);
+    assert_eq!(Component::new("VCARD").iter_props().count(), 0);
+}
+
+#[test]
+fn test_merge() {
+    use vobject::{MergeStrategy, MergeSummary, MultiValued};
+
+    let local = parse_component(
+            "BEGIN:VCARD\n\
+            VERSION:4.0\n\
+            UID:local-uid\n\
+            N:Mustermann;Erika;;;\n\
+            TEL;TYPE=work:+49 221 9999123\n\
+            EMAIL:erika@example.com\n\
+            END:VCARD\n").unwrap();
+    let incoming = parse_component(
+            "BEGIN:VCARD\n\
+            VERSION:4.0\n\
+            UID:remote-uid\n\
+            N:Gabler;Erika;;;\n\
+            TEL;TYPE=home:+49 221 1234567\n\
+            TEL;TYPE=work:+49 221 9999123\n\
+            NOTE:Met at the conference\n\
+            END:VCARD\n").unwrap();
+
+    let mut merged = local.clone();
+    let summary = merged.merge(incoming.clone(), MergeStrategy::default());
+    assert_eq!(summary, MergeSummary {
+        added: vec![s!("TEL"), s!("NOTE")],
+        replaced: vec![s!("N")],
+        ..MergeSummary::default()
+    });
+    assert_eq!(merged.get_value("UID").unwrap(), "local-uid");
+    assert_eq!(merged.get_value("N").unwrap(), "Gabler;Erika;;;");
+    assert_eq!(merged.get_all_values("TEL"), vec!["+49 221 9999123", "+49 221 1234567"]);
+    assert_eq!(merged.get_all_values("EMAIL"), vec!["erika@example.com"]);
+
+    // Merging again changes nothing.
+    assert!(merged.merge(incoming.clone(), MergeStrategy::default()).is_empty());
+
+    let mut replaced = local.clone();
+    let strategy = MergeStrategy { multi_valued: MultiValued::Replace, keep_other_uid: true };
+    let summary = replaced.merge(incoming.clone(), strategy);
+    assert_eq!(summary.replaced, vec![s!("UID"), s!("N"), s!("TEL")]);
+    assert_eq!(replaced.get_value("UID").unwrap(), "remote-uid");
+    assert_eq!(replaced.get_all_values("TEL"), vec!["+49 221 1234567", "+49 221 9999123"]);
+    assert_eq!(replaced.get_all_values("EMAIL"), vec!["erika@example.com"]);
+}
+
+#[test]
+fn test_merge_subcomponents() {
+    use vobject::MergeStrategy;
+
+    let mut local = parse_component(
+            "BEGIN:VCALENDAR\n\
+            BEGIN:VEVENT\n\
+            UID:1\n\
+            SUMMARY:Old title\n\
+            BEGIN:VALARM\n\
+            TRIGGER:-PT15M\n\
+            END:VALARM\n\
+            END:VEVENT\n\
+            END:VCALENDAR\n").unwrap();
+    let incoming = parse_component(
+            "BEGIN:VCALENDAR\n\
+            BEGIN:VEVENT\n\
+            UID:1\n\
+            SUMMARY:New title\n\
+            BEGIN:VALARM\n\
+            TRIGGER:-PT15M\n\
+            END:VALARM\n\
+            END:VEVENT\n\
+            BEGIN:VEVENT\n\
+            UID:2\n\
+            END:VEVENT\n\
+            END:VCALENDAR\n").unwrap();
+
+    let summary = local.merge(incoming, MergeStrategy::default());
+    assert_eq!(summary.subcomponents_added, vec![s!("VEVENT")]);
+    assert_eq!(summary.subcomponents_merged.len(), 1);
+    assert_eq!(summary.subcomponents_merged[0].1.replaced, vec![s!("SUMMARY")]);
+    assert!(summary.subcomponents_merged[0].1.subcomponents_added.is_empty());
+
+    assert_eq!(local.subcomponents.len(), 2);
+    assert_eq!(local.subcomponents[0].get_value("SUMMARY").unwrap(), "New title");
+    assert_eq!(local.subcomponents[0].subcomponents.len(), 1);
+}
+
+#[test]
+fn test_diff() {
+    use vobject::{diff, PropertyChange};
+
+    let a = parse_component(
+            "BEGIN:VCALENDAR\n\
+            BEGIN:VEVENT\n\
+            UID:1\n\
+            SUMMARY:Lunch\\, then coffee\n\
+            LOCATION:Cafeteria\n\
+            ATTENDEE;ROLE=CHAIR:mailto:a@example.com\n\
+            ATTENDEE:mailto:b@example.com\n\
+            END:VEVENT\n\
+            BEGIN:VEVENT\n\
+            UID:2\n\
+            END:VEVENT\n\
+            END:VCALENDAR\n").unwrap();
+    let b = parse_component(
+            "BEGIN:VCALENDAR\n\
+            BEGIN:VEVENT\n\
+            UID:1\n\
+            SUMMARY:Lunch\\, then coffee\n\
+            LOCATION:Roof terrace\n\
+            ATTENDEE:mailto:c@example.com\n\
+            ATTENDEE;ROLE=CHAIR:mailto:a@example.com\n\
+            DESCRIPTION:Bring sunscreen\n\
+            END:VEVENT\n\
+            BEGIN:VTODO\n\
+            UID:3\n\
+            END:VTODO\n\
+            END:VCALENDAR\n").unwrap();
+
+    assert!(diff(&a, &a).is_empty());
+
+    let d = diff(&a, &b);
+    assert!(d.added.is_empty() && d.removed.is_empty() && d.changed.is_empty());
+    assert_eq!(d.removed_subcomponents.len(), 1);
+    assert_eq!(d.removed_subcomponents[0].get_value("UID").unwrap(), "2");
+    assert_eq!(d.added_subcomponents.len(), 1);
+    assert_eq!(d.added_subcomponents[0].name, "VTODO");
+
+    assert_eq!(d.changed_subcomponents.len(), 1);
+    let (ref name, ref event) = d.changed_subcomponents[0];
+    assert_eq!(name, "VEVENT");
+    assert_eq!(event.changed, vec![
+        PropertyChange {
+            name: s!("LOCATION"),
+            params: Default::default(),
+            prop_group: None,
+            old_value: s!("Cafeteria"),
+            new_value: s!("Roof terrace"),
+        },
+        PropertyChange {
+            name: s!("ATTENDEE"),
+            params: Default::default(),
+            prop_group: None,
+            old_value: s!("mailto:b@example.com"),
+            new_value: s!("mailto:c@example.com"),
+        },
+    ]);
+    assert_eq!(event.added.len(), 1);
+    assert_eq!(event.added[0].name, "DESCRIPTION");
+    assert!(event.removed.is_empty());
+
+    // Escaping differences are not changes.
+    let mut escaped = Component::new("VCARD");
+    escaped.push(Property { raw_value: s!("a\\nb"), ..Property::new("NOTE", "") });
+    let mut other = Component::new("VCARD");
+    other.push(Property { raw_value: s!("a\\Nb"), ..Property::new("NOTE", "") });
+    assert!(diff(&escaped, &other).is_empty());
+}
+
+#[test]
+fn test_repeated_params() {
+    let item = parse_component(
+            "BEGIN:VCARD\r\n\
+            TEL;TYPE=work;PREF=1;type=voice,msg;TYPE=\"x-a,b\":+49 221 9999123\r\n\
+            EMAIL;INTERNET;INTERNET:erika@example.com\r\n\
+            END:VCARD\r\n").unwrap();
+
+    let tel = item.get_only("TEL").unwrap();
+    assert_eq!(tel.get_params_all("TYPE"), vec!["work", "voice", "msg", "x-a,b"]);
+    assert_eq!(tel.get_params_all("pref"), vec!["1"]);
+    assert!(tel.get_params_all("VALUE").is_empty());
+    assert_eq!(tel.get_param("TYPE"), Some("work"));
+    assert!(item.get_only("EMAIL").unwrap().get_params_all("INTERNET").is_empty());
+
+    assert_eq!(tel.param_breaks.get("TYPE"), Some(&vec![1, 3]));
+
+    // Each occurrence is written back as it was given, without the original order of the
+    // parameters, which are sorted by name.
+    assert_eq!(write_component(&item),
+               "BEGIN:VCARD\r\n\
+               TEL;PREF=1;TYPE=work;TYPE=voice,msg;TYPE=\"x-a,b\":+49 221 9999123\r\n\
+               EMAIL;INTERNET:erika@example.com\r\n\
+               END:VCARD\r\n");
+
+    // Setting a parameter replaces all its occurrences.
+    let mut tel = tel.clone();
+    tel.set_param("type", "home");
+    assert!(tel.param_breaks.is_empty());
+    assert_eq!(tel.get_params_all("TYPE"), vec!["home"]);
+}
+
+#[test]
+fn test_same_name_order_and_pref() {
+    let mut item = parse_component(
+            "BEGIN:VCARD\r\n\
+            EMAIL:first@example.com\r\n\
+            FN:Erika Mustermann\r\n\
+            EMAIL;PREF=2:second@example.com\r\n\
+            TEL:+49 221 9999123\r\n\
+            EMAIL:third@example.com\r\n\
+            EMAIL;PREF=1:fourth@example.com\r\n\
+            EMAIL;PREF=x:fifth@example.com\r\n\
+            END:VCARD\r\n").unwrap();
+
+    assert_eq!(item.get_all_values("EMAIL"),
+               vec!["first@example.com", "second@example.com", "third@example.com",
+                    "fourth@example.com", "fifth@example.com"]);
+
+    item.sort_props_by_pref("email");
+    assert_eq!(item.get_all_values("EMAIL"),
+               vec!["fourth@example.com", "second@example.com", "first@example.com",
+                    "third@example.com", "fifth@example.com"]);
+    item.sort_props_by_pref("NOTE");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_roundtrip() {
+    let c = parse_component("BEGIN:VCALENDAR\n\
+                             VERSION:2.0\n\
+                             BEGIN:VEVENT\n\
+                             UID:1234\n\
+                             item1.SUMMARY;LANGUAGE=de;X-A=b,c:Mittag\\, vielleicht\n\
+                             END:VEVENT\n\
+                             END:VCALENDAR\n").unwrap();
+
+    let json = serde_json::to_string(&c).unwrap();
+    let back: Component = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, c);
+    assert_eq!(write_component(&back), write_component(&c));
+}
+
+#[test]
+fn test_empty_value() {
+    let input = "BEGIN:VEVENT\r\n\
+                 DESCRIPTION:\r\n\
+                 item1.X-ABLABEL:\r\n\
+                 SUMMARY;LANGUAGE=en:\r\n\
+                 END:VEVENT\r\n";
+    let item = parse_component(input).unwrap();
+
+    assert_eq!(item.get_only("DESCRIPTION").unwrap().raw_value, "");
+    assert_eq!(item.get_only("X-ABLABEL").unwrap().prop_group, Some(s!("item1")));
+    assert_eq!(item.get_only("X-ABLABEL").unwrap().raw_value, "");
+    assert_eq!(item.get_only("SUMMARY").unwrap().value_as_string(), "");
+    assert_eq!(write_component(&item), input);
+}
+
+#[test]
+fn test_vcard21_bare_types() {
+    let input = "BEGIN:VCARD\r\n\
+                 VERSION:2.1\r\n\
+                 TEL;WORK;VOICE;PREF:+1 555 1234\r\n\
+                 TEL;TYPE=HOME;fax:+1 555 4321\r\n\
+                 NOTE;QUOTED-PRINTABLE:Hi\r\n\
+                 END:VCARD\r\n";
+
+    let item = parse_component(input).unwrap();
+    let tel = &item.get_all("TEL")[0];
+    assert!(tel.params["WORK"].is_empty());
+    assert_eq!(tel.get_param("TYPE"), None);
+
+    let options = ParseOptions { vcard21: true, ..ParseOptions::default() };
+    let item = parse_component_with(input, &options).unwrap();
+    let tels = item.get_all("TEL");
+    assert_eq!(tels[0].get_params_all("TYPE"), ["WORK", "VOICE", "PREF"]);
+    assert_eq!(tels[0].params.len(), 1);
+    assert_eq!(tels[1].get_params_all("TYPE"), ["HOME", "fax"]);
+    assert!(item.get_only("NOTE").unwrap().params["QUOTED-PRINTABLE"].is_empty());
+}
+
+#[test]
+fn test_version() {
+    use vobject::{Vcard, Version};
+
+    let card = parse_component("BEGIN:VCARD\nVERSION:4.0\nFN:Erika\nEND:VCARD\n").unwrap();
+    assert_eq!(card.version(), Some(Version::V4_0));
+    let cal = parse_component("BEGIN:VCALENDAR\nVERSION:2.0\nEND:VCALENDAR\n").unwrap();
+    assert_eq!(cal.version(), Some(Version::ICal2_0));
+    let card = parse_component("BEGIN:VCARD\nVERSION:9\nEND:VCARD\n").unwrap();
+    assert_eq!(card.version(), None);
+    assert_eq!(Component::new("VCARD").version(), None);
+
+    let card = Vcard::build("BEGIN:VCARD\nVERSION:2.1\nN:Doe\nTEL;WORK:123\nEND:VCARD\n").unwrap();
+    assert_eq!(card.get_only("TEL").unwrap().get_param("TYPE"), Some("WORK"));
+    let card = Vcard::build("BEGIN:VCARD\nVERSION:3.0\nN:Doe\nTEL;WORK:123\nEND:VCARD\n").unwrap();
+    assert_eq!(card.get_only("TEL").unwrap().get_param("TYPE"), None);
+}
+
+#[test]
+fn test_icalendar_timezone() {
+    use vobject::ICalendar;
+
+    let cal = ICalendar::build("BEGIN:VCALENDAR\r\n\
+                                VERSION:2.0\r\n\
+                                BEGIN:VTIMEZONE\r\n\
+                                TZID:America/New_York\r\n\
+                                BEGIN:STANDARD\r\n\
+                                DTSTART:20071104T020000\r\n\
+                                TZOFFSETFROM:-0400\r\n\
+                                TZOFFSETTO:-0500\r\n\
+                                RRULE:FREQ=YEARLY;BYMONTH=11;BYDAY=1SU\r\n\
+                                END:STANDARD\r\n\
+                                BEGIN:DAYLIGHT\r\n\
+                                DTSTART:20070311T020000\r\n\
+                                TZOFFSETFROM:-0500\r\n\
+                                TZOFFSETTO:-0400\r\n\
+                                RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=2SU\r\n\
+                                END:DAYLIGHT\r\n\
+                                END:VTIMEZONE\r\n\
+                                BEGIN:VEVENT\r\n\
+                                UID:1\r\n\
+                                DTSTART;TZID=America/New_York:20240704T090000\r\n\
+                                DTEND;TZID=Europe/Vienna:20240704T190000\r\n\
+                                END:VEVENT\r\n\
+                                END:VCALENDAR\r\n").unwrap();
+
+    assert!(cal.timezone("America/New_York").is_some());
+    assert!(cal.timezone("Europe/Vienna").is_none());
+
+    let event = &cal.subcomponents[1];
+    assert_eq!(cal.utc_offset(event.get_only("DTSTART").unwrap()), Some(-4 * 3600));
+    assert_eq!(cal.utc_offset(event.get_only("DTEND").unwrap()), None);
+    let mut winter = Property::new("DTSTART", "20241225T090000");
+    winter.set_param("TZID", "America/New_York");
+    assert_eq!(cal.utc_offset(&winter), Some(-5 * 3600));
+}
+
+#[test]
+fn test_geo() {
+    let geo = |value: &str| {
+        let mut prop = Property::new("GEO", "");
+        prop.raw_value = value.to_owned();
+        prop.value_as_geo()
+    };
+
+    assert_eq!(geo("37.386013;-122.082932"), Some((37.386013, -122.082932)));
+    assert_eq!(geo("-33.8688;151.2093"), Some((-33.8688, 151.2093)));
+    assert_eq!(geo("geo:37.386013,-122.082932"), Some((37.386013, -122.082932)));
+    assert_eq!(geo("GEO:-33.8688,151.2093,58;u=10"), Some((-33.8688, 151.2093)));
+    for invalid in &["", "37.386", "37.386,-122.082", "a;b", "1;2;3", "geo:1", "geo:1;2", "91;0", "0;-181"] {
+        assert_eq!(geo(invalid), None, "{:?} parsed", invalid);
+    }
+
+    let mut card = parse_component("BEGIN:VCARD\nVERSION:4.0\nFN:Erika\nGEO:1;2\nEND:VCARD\n").unwrap();
+    card.set_geo(-33.8688, 151.2093);
+    assert_eq!(card.get_all("GEO").len(), 1);
+    assert_eq!(card.get_only("GEO").unwrap().raw_value, "geo:-33.8688,151.2093");
+    let mut event = Component::new("VEVENT");
+    event.set_geo(37.5, -122.25);
+    assert_eq!(event.get_only("GEO").unwrap().raw_value, "37.5;-122.25");
+    assert_eq!(event.get_only("GEO").unwrap().value_as_geo(), Some((37.5, -122.25)));
+}
+
+#[test]
+fn test_param_value_rfc6868() {
+    use vobject::{escape_param_value, unescape_param_value};
+
+    assert_eq!(escape_param_value("a^b"), "a^^b");
+    assert_eq!(escape_param_value("a\nb\r\nc"), "a^nb^nc");
+    assert_eq!(escape_param_value("say \"hi\""), "say ^'hi^'");
+    assert_eq!(unescape_param_value("a^^b^nc^'d^'"), "a^b\nc\"d\"");
+    assert_eq!(unescape_param_value("a^Nb^xc^"), "a\nb^xc^");
+
+    for value in &["2^3", "Line one\nLine two", "The \"best\" one", "^n^'\"\n^"] {
+        let mut prop = Property::new("X-TEST", "x");
+        prop.set_param("X-PARAM", value);
+        let mut c = Component::new("VCARD");
+        c.push(prop);
+
+        let written = write_component(&c);
+        assert!(!written.contains("\n\n") && written.lines().count() == 3, "{:?}", written);
+        let parsed = parse_component(&written).unwrap();
+        assert_eq!(parsed.get_only("X-TEST").unwrap().get_param("X-PARAM"), Some(*value));
+    }
+
+    let c = parse_component("BEGIN:VCARD\nADR;LABEL=\"123 Main St.^nAnytown\":;;123 Main St.;;;;\nEND:VCARD\n").unwrap();
+    assert_eq!(c.get_only("ADR").unwrap().get_param("LABEL"), Some("123 Main St.\nAnytown"));
+    assert_eq!(write_component(&c), "BEGIN:VCARD\r\nADR;LABEL=123 Main St.^nAnytown:;;123 Main St.;;;;\r\nEND:VCARD\r\n");
+}
+
+#[test]
+fn test_parse_component_bytes() {
+    use vobject::parse_component_bytes;
+
+    let mut input = b"BEGIN:VCARD\r\nVERSION:2.1\r\nN;CHARSET=ISO-8859-1:M\xfcller;J\xfcrgen\r\n".to_vec();
+    input.extend_from_slice(b"NOTE:Gr\xfc\xdfe \x80\r\n  und Tsch\xfcss\r\nFN:J\xc3\xbcrgen\r\nEND:VCARD\r\n");
+    let card = parse_component_bytes(&input).unwrap();
+
+    let n = card.get_only("N").unwrap();
+    assert_eq!(n.value_as_components(), ["Müller", "Jürgen"]);
+    assert_eq!(n.get_param("CHARSET"), Some("ISO-8859-1"));
+    assert_eq!(n.value_decoded_as_string().unwrap(), "Müller;Jürgen");
+    assert_eq!(card.get_only("NOTE").unwrap().raw_value, "Grüße € und Tschüss");
+    assert_eq!(card.get_only("FN").unwrap().raw_value, "Jürgen");
+
+    let utf8 = "BEGIN:VCARD\nFN:Jürgen\nEND:VCARD\n";
+    assert_eq!(parse_component_bytes(utf8.as_bytes()).unwrap(), parse_component(utf8).unwrap());
+    assert!(parse_component_bytes(b"BEGIN:VCARD\nFN:J\xfcrgen\n").is_err());
+}
+
+#[test]
+fn test_x_properties_roundtrip() {
+    let cards = [
+        // Apple Contacts
+        "BEGIN:VCARD\r\n\
+         VERSION:3.0\r\n\
+         PRODID:-//Apple Inc.//Mac OS X 10.15.7//EN\r\n\
+         N:Appleseed;Johnny;;;\r\n\
+         FN:Johnny Appleseed\r\n\
+         item1.EMAIL;type=INTERNET;type=pref:johnny@example.com\r\n\
+         item1.X-ABLabel:_$!<Other>!$_\r\n\
+         item2.ADR;type=HOME;type=pref:;;1 Infinite Loop;Cupertino;CA;95014;USA\r\n\
+         item2.X-ABADR:us\r\n\
+         item3.X-ABRELATEDNAMES;type=pref:Jane\r\n\
+         item3.X-ABLabel:_$!<Spouse>!$_\r\n\
+         X-APPLE-SUBLOCALITY:Downtown\r\n\
+         X-ABUID:5AD380FD-B2DE-4261-BA99-DE1D1DB52FBE:ABPerson\r\n\
+         X-APPLE-STRUCTURED-LOCATION;VALUE=URI;X-ADDRESS=\"1 Infinite Loop, Cupertino\";\r\n \
+          X-APPLE-RADIUS=49;X-TITLE=Apple:geo:37.331,-122.030\r\n\
+         X-SOCIALPROFILE;type=twitter;x-user=johnny:http://twitter.com/johnny\r\n\
+         END:VCARD\r\n",
+        // Android and Google exports
+        "BEGIN:VCARD\r\n\
+         VERSION:2.1\r\n\
+         N:Mustermann;Erika;;;\r\n\
+         X-ANDROID-CUSTOM:vnd.android.cursor.item/nickname;Eri;1;;;;;;;;;;;;;\r\n\
+         X-PHONETIC-FIRST-NAME:Erika\r\n\
+         X-GOOGLE-TALK;TYPE=HOME:erika@example.com\r\n\
+         X-CUSTOM;X-EMPTY=;X-BARE;X-CARET=a^^b:value with \\, a comma\r\n\
+         x-lowercase:still here\r\n\
+         X-EVOLUTION-FILE-AS:Mustermann\\, Erika\r\n\
+         END:VCARD\r\n",
+    ];
+
+    for input in &cards {
+        let original = parse_component(input).unwrap();
+        let written = write_component(&original);
+        let reparsed = parse_component(&written).unwrap();
+        assert_eq!(reparsed, original, "written as {}", written);
+        assert_eq!(write_component(&reparsed), written);
+
+        let x_props = |c: &Component| {
+            c.props.values()
+                .flat_map(|props| props.iter())
+                .filter(|p| p.name.to_uppercase().starts_with("X-"))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        let expected = x_props(&original);
+        assert!(!expected.is_empty());
+        assert_eq!(x_props(&reparsed), expected);
+    }
+
+    let card = parse_component(cards[0]).unwrap();
+    let labels = card.get_all("X-ABLABEL");
+    assert_eq!(labels[0].name, "X-ABLabel");
+    assert_eq!(labels[0].prop_group, Some(s!("item1")));
+    assert_eq!(labels[0].raw_value, "_$!<Other>!$_");
+    let location = card.get_only("X-APPLE-STRUCTURED-LOCATION").unwrap();
+    assert_eq!(location.get_param("X-ADDRESS"), Some("1 Infinite Loop, Cupertino"));
+    assert_eq!(location.raw_value, "geo:37.331,-122.030");
+    assert!(write_component(&card).contains("item1.X-ABLabel:_$!<Other>!$_\r\n"));
+}
+
+#[test]
+fn test_prop_count() {
+    let mut c = Component::new("VCARD");
+    assert!(c.is_empty());
+    assert_eq!(c.prop_count(), 0);
+    assert!(!c.has_prop("TEL"));
+
+    c.push(Property::new("TEL", "1"));
+    c.push(Property::new("tel", "2"));
+    c.push(Property::new("FN", "Erika"));
+    assert_eq!(c.prop_count(), 3);
+    assert!(c.has_prop("Tel"));
+    assert!(!c.is_empty());
+
+    c.remove("TEL");
+    c.remove("FN");
+    assert!(!c.has_prop("TEL"));
+    assert!(c.is_empty());
+    c.add_subcomponent(Component::new("X-CHILD"));
+    assert!(!c.is_empty());
+}
+
+#[test]
+fn test_strict_line_length() {
+    let long = format!("BEGIN:VCARD\r\nVERSION:4.0\r\nNOTE:{}\r\nEND:VCARD\r\n", "x".repeat(71));
+    let options = ParseOptions { strict_line_length: true, ..ParseOptions::default() };
+
+    assert!(parse_component(&long).is_ok());
+    let e = parse_component_with(&long, &options).unwrap_err();
+    assert_eq!(*e.kind(), ParseErrorKind::LineTooLong(76));
+    assert_eq!(e.line(), 3);
+    assert!(vobject::parse_components_with(&long, &options).is_err());
+
+    // The writer folds at 75 octets, so its output passes.
+    let mut card = Component::new("VCARD");
+    card.push(Property::new("NOTE", "ü".repeat(100)));
+    assert!(parse_component_with(&write_component(&card), &options).is_ok());
+    let exact = format!("BEGIN:VCARD\r\nNOTE:{}\r\n {}\r\nEND:VCARD", "x".repeat(70), "y".repeat(74));
+    assert!(parse_component_with(&exact, &options).is_ok());
+}
+
+#[test]
+fn test_component_reader_with_options() {
+    use std::io::Cursor;
+    use vobject::ComponentReader;
+
+    let input = "BEGIN:VCARD\nVERSION:2.1\nTEL;WORK:123\nEND:VCARD\n\
+                 BEGIN:VCARD\nVERSION:2.1\nNOTE:x\nEND:VCARD\n";
+    let options = ParseOptions { vcard21: true, ..ParseOptions::default() };
+    let cards = ComponentReader::with_options(Cursor::new(input), &options)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(cards.len(), 2);
+    assert_eq!(cards[0].get_only("TEL").unwrap().get_param("TYPE"), Some("WORK"));
+    assert_eq!(cards, vobject::parse_components_with(input, &options).unwrap());
+}
+
+#[test]
+fn test_write_options_params() {
+    let mut prop = Property::new("ATTENDEE", "mailto:a@example.com");
+    prop.set_param("ROLE", "CHAIR");
+    prop.set_param("CN", "Jane \"JJ\" Doe");
+    let mut event = Component::new("VEVENT");
+    event.push(prop);
+
+    let written = write_component(&event);
+    assert!(written.contains("ATTENDEE;CN=Jane ^'JJ^' Doe;ROLE=CHAIR:mailto:"), "{}", written);
+
+    let options = WriteOptions { escape_param_values: false, ..WriteOptions::default() };
+    let written = write_component_with(&event, &options);
+    assert!(written.contains("ATTENDEE;CN=Jane \"JJ\" Doe;ROLE=CHAIR:mailto:"), "{}", written);
+}
+
+#[test]
+fn test_has_param_value() {
+    let card = parse_component("BEGIN:VCARD\n\
+                                TEL;TYPE=WORK,voice;type=Pref:+1 555 1234\n\
+                                TEL;TYPE=\"home,Fax\":+1 555 4321\n\
+                                TEL:+1 555 0000\n\
+                                END:VCARD\n").unwrap();
+    let tels = card.get_all("TEL");
+
+    assert!(tels[0].has_param_value("TYPE", "work"));
+    assert!(tels[0].has_param_value("type", "VOICE"));
+    assert!(tels[0].has_param_value("Type", "pref"));
+    assert!(!tels[0].has_param_value("TYPE", "home"));
+    assert!(tels[1].has_param_value("TYPE", "HOME"));
+    assert!(tels[1].has_param_value("TYPE", "fax"));
+    assert!(!tels[1].has_param_value("TYPE", "home,fax"));
+    assert!(!tels[2].has_param_value("TYPE", "work"));
+    assert!(!tels[0].has_param_value("VALUE", "work"));
+}
+
+#[test]
+fn test_events_with_context() {
+    use vobject::ICalendar;
+
+    let cal = ICalendar::build("BEGIN:VCALENDAR\n\
+                                VERSION:2.0\n\
+                                PRODID:-//Example//EN\n\
+                                METHOD:REQUEST\n\
+                                BEGIN:VTIMEZONE\n\
+                                TZID:Europe/Vienna\n\
+                                END:VTIMEZONE\n\
+                                BEGIN:VTIMEZONE\n\
+                                TZID:America/New_York\n\
+                                END:VTIMEZONE\n\
+                                BEGIN:VEVENT\n\
+                                UID:1\n\
+                                DTSTART;TZID=Europe/Vienna:20240101T090000\n\
+                                DTEND;TZID=Europe/Vienna:20240101T100000\n\
+                                EXDATE;TZID=Asia/Tokyo:20240108T090000\n\
+                                END:VEVENT\n\
+                                BEGIN:VTODO\n\
+                                UID:2\n\
+                                END:VTODO\n\
+                                BEGIN:VEVENT\n\
+                                UID:3\n\
+                                DTSTART:20240101T090000Z\n\
+                                END:VEVENT\n\
+                                END:VCALENDAR\n").unwrap();
+
+    let events = cal.events_with_context().collect::<Vec<_>>();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].event.get_only("UID").unwrap().raw_value, "1");
+    assert_eq!(events[0].prodid.unwrap().raw_value, "-//Example//EN");
+    assert_eq!(events[0].method.unwrap().raw_value, "REQUEST");
+    assert!(events[0].calscale.is_none());
+    assert_eq!(events[0].timezones.len(), 1);
+    assert_eq!(events[0].timezones[0].get_only("TZID").unwrap().raw_value, "Europe/Vienna");
+    assert_eq!(events[1].event.get_only("UID").unwrap().raw_value, "3");
+    assert!(events[1].timezones.is_empty());
+}
+
+#[test]
+fn test_canonical_string() {
+    let a = parse_component("BEGIN:VCARD\r\n\
+                             VERSION:4.0\r\n\
+                             FN:Erika Mustermann\r\n\
+                             item1.TEL;TYPE=work,voice;PREF=1:+49 221 9999123\r\n\
+                             NOTE:line\\Nbreak\\;x\\:y\r\n\
+                             END:VCARD\r\n").unwrap();
+    let b = parse_component("BEGIN:vcard\n\
+                             ITEM1.tel;pref=1;type=voice;TYPE=work:+49 221 9999123\n\
+                             note:line\\nbreak\\;x\\\\:y\n\
+                             fn:Erika Mustermann\n\
+                             version:4.0\n\
+                             END:vcard\n").unwrap();
+
+    assert_eq!(a.canonical_string(), b.canonical_string());
+    assert_eq!(a.canonical_string(),
+               "BEGIN:VCARD\r\n\
+                FN:Erika Mustermann\r\n\
+                NOTE:line\\nbreak\\;x\\\\:y\r\n\
+                ITEM1.TEL;PREF=1;TYPE=voice,work:+49 221 9999123\r\n\
+                VERSION:4.0\r\n\
+                END:VCARD\r\n");
+    assert_eq!(parse_component(&a.canonical_string()).unwrap().canonical_string(), a.canonical_string());
+
+    let mut c = a.clone();
+    c.set(Property::new("FN", "Erika M."));
+    assert_ne!(a.canonical_string(), c.canonical_string());
+
+    let cal = |first, second| format!("BEGIN:VCALENDAR\nBEGIN:VEVENT\nUID:{}\nEND:VEVENT\n\
+                                       BEGIN:VEVENT\nUID:{}\nEND:VEVENT\nEND:VCALENDAR\n",
+                                      first, second);
+    assert_eq!(parse_component(&cal(1, 2)).unwrap().canonical_string(),
+               parse_component(&cal(2, 1)).unwrap().canonical_string());
+}
+
+#[test]
+fn test_localized_values() {
+    let event = parse_component("BEGIN:VEVENT\n\
+                                 SUMMARY:Meeting\n\
+                                 SUMMARY;LANGUAGE=de;ALTREP=\"CID:part3.msg@example.com\":Besprechung\n\
+                                 SUMMARY;LANGUAGE=fr:Réunion\\, salle 2\n\
+                                 DESCRIPTION;LANGUAGE=de:Nur deutsch\n\
+                                 END:VEVENT\n").unwrap();
+
+    let summaries = event.get_all("SUMMARY");
+    assert_eq!(summaries[0].language(), None);
+    assert_eq!(summaries[1].language(), Some("de"));
+    assert_eq!(summaries[1].altrep(), Some("CID:part3.msg@example.com"));
+    assert_eq!(summaries[2].altrep(), None);
+
+    assert_eq!(event.get_value_localized("SUMMARY", "DE").as_deref(), Some("Besprechung"));
+    assert_eq!(event.get_value_localized("summary", "fr").as_deref(), Some("Réunion, salle 2"));
+    assert_eq!(event.get_value_localized("SUMMARY", "it").as_deref(), Some("Meeting"));
+    assert_eq!(event.get_value_localized("DESCRIPTION", "en"), None);
+    assert_eq!(event.get_value_localized("LOCATION", "de"), None);
+}
+
+#[test]
+fn test_source_spans() {
+    let input = "\u{feff}BEGIN:VCARD\r\n\
+                 FN:Erika\r\n\
+                 item1.NOTE;LANGUAGE=de:folded\r\n  across\r\n\tlines\r\n\
+                 TEL:123\n\
+                 END:VCARD\r\n";
+    let options = ParseOptions { source_spans: true, ..ParseOptions::default() };
+    let card = parse_component_with(input, &options).unwrap();
+    let span = |name| card.get_only(name).unwrap().source_span.unwrap();
+    let text = |name| {
+        let (start, end) = span(name);
+        &input[start..end]
+    };
+
+    assert_eq!(text("FN"), "FN:Erika");
+    assert_eq!(text("NOTE"), "item1.NOTE;LANGUAGE=de:folded\r\n  across\r\n\tlines");
+    assert_eq!(text("TEL"), "TEL:123");
+    assert_eq!(card.get_value("NOTE").unwrap(), "folded acrosslines");
+
+    let plain = parse_component(input).unwrap();
+    assert_eq!(plain.get_only("FN").unwrap().source_span, None);
+    assert_eq!(plain, card);
+}
+
+#[test]
+fn test_groups() {
+    let card = parse_component("BEGIN:VCARD\n\
+                                VERSION:3.0\n\
+                                FN:Erika\n\
+                                item1.TEL:+49 221 9999123\n\
+                                item1.X-ABLabel:_$!<Mobile>!$_\n\
+                                item2.EMAIL:erika@example.com\n\
+                                item2.X-ABLabel:Work\n\
+                                END:VCARD\n").unwrap();
+
+    assert_eq!(card.groups(), ["item1", "item2"]);
+    let item1 = card.props_in_group("item1");
+    assert_eq!(item1.len(), 2);
+    assert_eq!(item1[0].name, "TEL");
+    assert_eq!(item1[1].raw_value, "_$!<Mobile>!$_");
+    let item2 = card.props_in_group("Item2");
+    assert_eq!(item2.iter().map(|p| &p.name[..]).collect::<Vec<_>>(), ["X-ABLabel", "EMAIL"]);
+    assert!(card.props_in_group("item3").is_empty());
+    assert!(Component::new("VCARD").groups().is_empty());
+}
+
+#[test]
+fn test_mismatched_end() {
+    // END names are matched case-insensitively.
+    let event = parse_component("BEGIN:VEVENT\nSUMMARY:Meeting\nEND:vevent\n").unwrap();
+    assert_eq!(event.name, "VEVENT");
+
+    // A nested component closed by its parent's END is reported where the END is.
+    let e = parse_component("BEGIN:VCALENDAR\n\
+                             BEGIN:VEVENT\n\
+                             SUMMARY:Meeting\n\
+                             END:VCALENDAR\n").unwrap_err();
+    assert_eq!(*e.kind(), ParseErrorKind::MismatchedEnd { expected: s!("VEVENT"), found: s!("VCALENDAR") });
+    assert_eq!(e.line(), 4);
+}
+
+#[test]
+fn test_quoted_param_across_folds() {
+    let cases = [
+        // Inside the quoted value, with CRLF and LF line endings.
+        "ATTENDEE;CN=\"Erika Muster\r\n mann; Sales: EMEA\":mailto:erika@example.com",
+        "ATTENDEE;CN=\"Erika Muster\n\tmann; Sales: EMEA\":mailto:erika@example.com",
+        // Right after the opening quote and right before the closing one.
+        "ATTENDEE;CN=\"\r\n Erika Mustermann; Sales: EMEA\r\n \":mailto:erika@example.com",
+        // Before the opening quote, and several times in a row.
+        "ATTENDEE;CN=\r\n \"Erika \r\n Mu\r\n st\r\n ermann; Sales: EMEA\":mailto:erika@example.com",
+    ];
+
+    for line in &cases {
+        let input = format!("BEGIN:VEVENT\r\n{}\r\nEND:VEVENT\r\n", line);
+        let event = parse_component(&input).unwrap();
+        let attendee = event.get_only("ATTENDEE").unwrap();
+        assert_eq!(attendee.get_param("CN"), Some("Erika Mustermann; Sales: EMEA"), "{:?}", line);
+        assert_eq!(attendee.raw_value, "mailto:erika@example.com", "{:?}", line);
+    }
+
+    // The writer folds long quoted values, and they read back intact.
+    let mut event = Component::new("VEVENT");
+    let mut attendee = Property::new("ATTENDEE", "mailto:erika@example.com");
+    let cn = "Erika Mustermann, Head of Sales: Europe, Middle East; and Africa (EMEA)";
+    attendee.set_param("CN", cn);
+    event.push(attendee);
+    let written = write_component(&event);
+    assert!(written.lines().count() > 3);
+    let event = parse_component(&written).unwrap();
+    assert_eq!(event.get_only("ATTENDEE").unwrap().get_param("CN"), Some(cn));
+}
+
+#[test]
+fn test_subcomponents_mut() {
+    let mut cal = parse_component("BEGIN:VCALENDAR\r\n\
+                                   BEGIN:VEVENT\r\n\
+                                   DTSTART:20240101T090000Z\r\n\
+                                   END:VEVENT\r\n\
+                                   BEGIN:VTODO\r\n\
+                                   DTSTART:20240101T090000Z\r\n\
+                                   END:VTODO\r\n\
+                                   BEGIN:VEVENT\r\n\
+                                   DTSTART:20240102T090000Z\r\n\
+                                   END:VEVENT\r\n\
+                                   END:VCALENDAR\r\n").unwrap();
+
+    for event in cal.subcomponents_mut("vevent") {
+        let start = event.get_only("DTSTART").unwrap().raw_value.replace("T09", "T10");
+        event.set(Property::new("DTSTART", start));
+    }
+    assert_eq!(cal.subcomponents_mut("VJOURNAL").count(), 0);
+
+    assert_eq!(write_component(&cal),
+               "BEGIN:VCALENDAR\r\n\
+                BEGIN:VEVENT\r\n\
+                DTSTART:20240101T100000Z\r\n\
+                END:VEVENT\r\n\
+                BEGIN:VTODO\r\n\
+                DTSTART:20240101T090000Z\r\n\
+                END:VTODO\r\n\
+                BEGIN:VEVENT\r\n\
+                DTSTART:20240102T100000Z\r\n\
+                END:VEVENT\r\n\
+                END:VCALENDAR\r\n");
+}
+
+#[test]
+fn test_fold_whitespace_is_exact() {
+    use std::io::Cursor;
+
+    // As exported by Outlook: a space before the fold, a continuation indented by more than
+    // the one folding character, a doubled CR, and trailing whitespace at the end of values.
+    let input = "BEGIN:VEVENT\r\n\
+                 SUMMARY:Weekly \r\n sync \r\n  with the team  \r\n\
+                 DESCRIPTION:Agenda:\\n\r\r\n\t- budget\\n \r\n\t\t- hiring\r\n\
+                 LOCATION:Room 1 \r\n\
+                 END:VEVENT\r\n";
+    let check = |event: &Component| {
+        assert_eq!(event.get_value("SUMMARY").unwrap(), "Weekly sync  with the team  ");
+        assert_eq!(event.get_value("DESCRIPTION").unwrap(), "Agenda:\n- budget\n \t- hiring");
+        assert_eq!(event.get_value("LOCATION").unwrap(), "Room 1 ");
+    };
+
+    check(&parse_component(input).unwrap());
+    check(&vobject::parse_component_bytes(input.as_bytes()).unwrap());
+    let from_reader = parse_components_from_reader(Cursor::new(input)).unwrap();
+    check(&from_reader[0]);
+
+    // A blank line ends the content line, even if the next line starts with whitespace.
+    assert!(parse_component("BEGIN:VEVENT\r\nSUMMARY:a\r\n\r\n b\r\nEND:VEVENT\r\n").is_err());
+
+    // Values survive a write and parse unchanged, whitespace and all.
+    let event = parse_component(input).unwrap();
+    check(&parse_component(&write_component(&event)).unwrap());
+}
+
+#[test]
+fn test_parse_component_from_file() {
+    use std::fs;
+    use vobject::error::VObjectError;
+    use vobject::parse_component_from_file;
+
+    let dir = std::env::temp_dir();
+    let crlf = dir.join(format!("vobject-test-{}-crlf.vcf", std::process::id()));
+    let lf = dir.join(format!("vobject-test-{}-lf.vcf", std::process::id()));
+    fs::write(&crlf, "\u{feff}BEGIN:VCARD\r\nVERSION:3.0\r\nFN:Erika\r\n Mustermann\r\nEND:VCARD\r\n").unwrap();
+    fs::write(&lf, "BEGIN:VCARD\nVERSION:3.0\nFN:Erika\n Mustermann\nEND:VCARD\n").unwrap();
+
+    let from_crlf = parse_component_from_file(&crlf).unwrap();
+    let from_lf = parse_component_from_file(&lf).unwrap();
+    assert_eq!(from_crlf.get_value("FN").unwrap(), "ErikaMustermann");
+    assert_eq!(from_crlf, from_lf);
+
+    fs::write(&lf, "BEGIN:VCARD\nFN:Erika\n").unwrap();
+    match parse_component_from_file(&lf) {
+        Err(VObjectError::Parse { .. }) => {},
+        other => panic!("unexpected {:?}", other),
+    }
+    fs::remove_file(&crlf).unwrap();
+    fs::remove_file(&lf).unwrap();
+    match parse_component_from_file(&lf) {
+        Err(VObjectError::Io { .. }) => {},
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn test_write_component_to_file() {
+    use std::fs;
+    use vobject::{parse_component_from_file, write_component_to_file, write_component_to_file_with};
+
+    let dir = std::env::temp_dir().join(format!("vobject-test-{}-write", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("calendar.ics");
+    fs::write(&path, "old contents").unwrap();
+
+    let mut event = Component::new("VEVENT");
+    event.push(Property::new("SUMMARY", "x".repeat(100)));
+    write_component_to_file(&path, &event).unwrap();
+    let written = fs::read_to_string(&path).unwrap();
+    assert_eq!(written, write_component(&event));
+    assert!(written.contains("\r\n "));
+    assert_eq!(parse_component_from_file(&path).unwrap(), event);
+
+    let options = WriteOptions { line_ending: LineEnding::Lf, fold_width: None, ..WriteOptions::default() };
+    write_component_to_file_with(&path, &event, &options).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), write_component_with(&event, &options));
+
+    // No temporary files are left behind, and a failed write leaves nothing either.
+    assert!(write_component_to_file(dir.join("missing").join("x.ics"), &event).is_err());
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+    // Concurrent writes of the same path each use their own temporary file, so the result is
+    // always one complete component.
+    let events = (0..8)
+        .map(|i| {
+            let mut event = Component::new("VEVENT");
+            event.push(Property::new("SUMMARY", i.to_string().repeat(5000)));
+            event
+        })
+        .collect::<Vec<_>>();
+    let threads = events.iter().cloned().map(|event| {
+        let path = path.clone();
+        std::thread::spawn(move || {
+            for _ in 0..10 {
+                write_component_to_file(&path, &event).unwrap();
+            }
+        })
+    }).collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert!(events.contains(&parse_component_from_file(&path).unwrap()));
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_dedup_props() {
+    let mut card = parse_component("BEGIN:VCARD\n\
+                                    EMAIL;TYPE=WORK,home:erika@example.com\n\
+                                    EMAIL;type=HOME;type=work:erika@example.com\n\
+                                    EMAIL;TYPE=work:erika@example.com\n\
+                                    EMAIL;TYPE=work:other@example.com\n\
+                                    NOTE;X-TAG=a:Hi\\, there\n\
+                                    NOTE;X-TAG=A:Hi\\, there\n\
+                                    NOTE;x-tag=a:Hi\\, there\n\
+                                    item1.TEL:123\n\
+                                    ITEM1.TEL:123\n\
+                                    TEL:123\n\
+                                    END:VCARD\n").unwrap();
+    card.dedup_props();
+
+    let emails = card.get_all("EMAIL");
+    assert_eq!(emails.len(), 3);
+    assert_eq!(emails[0].get_params_all("TYPE"), ["WORK", "home"]);
+    assert_eq!(emails[1].get_params_all("TYPE"), ["work"]);
+    assert_eq!(emails[2].raw_value, "other@example.com");
+
+    // Values of unknown parameters stay case-sensitive.
+    let notes = card.get_all("NOTE");
+    assert_eq!(notes.iter().map(|p| p.get_param("X-TAG").unwrap()).collect::<Vec<_>>(), ["a", "A"]);
+    assert_eq!(card.get_all("TEL").len(), 2);
+}
+
+#[test]
+fn test_colons_in_values() {
+    let input = "BEGIN:VEVENT\r\n\
+                 ATTENDEE;CN=\"Doe: John\":mailto:foo@bar\r\n\
+                 URL:http://example.com:8080/a:b?c=d:e#f:g\r\n\
+                 DESCRIPTION:Time: 10:00\\, place: here::\r\n\
+                 X-EMPTY::\r\n\
+                 END:VEVENT\r\n";
+    let event = parse_component(input).unwrap();
+
+    let attendee = event.get_only("ATTENDEE").unwrap();
+    assert_eq!(attendee.raw_value, "mailto:foo@bar");
+    assert_eq!(attendee.get_param("CN"), Some("Doe: John"));
+    assert_eq!(event.get_only("URL").unwrap().raw_value, "http://example.com:8080/a:b?c=d:e#f:g");
+    assert_eq!(event.get_value("DESCRIPTION").unwrap(), "Time: 10:00, place: here::");
+    assert_eq!(event.get_only("X-EMPTY").unwrap().raw_value, ":");
+    assert_eq!(write_component(&event), input);
+
+    // Colons are never escaped when setting values.
+    let mut event = Component::new("VEVENT");
+    event.push(Property::new("URL", "http://example.com:8080/"));
+    event.push(Property::new("ATTENDEE", "mailto:foo@bar"));
+    let written = write_component(&event);
+    assert!(written.contains("\r\nURL:http://example.com:8080/\r\n"));
+    assert!(written.contains("\r\nATTENDEE:mailto:foo@bar\r\n"));
+    assert_eq!(parse_component(&written).unwrap(), event);
+}
+
+#[test]
+fn test_binary_data() {
+    let card = parse_component("BEGIN:VCARD\n\
+                                PHOTO;ENCODING=b;TYPE=JPEG:Zm9v\n\
+                                LOGO;VALUE=uri:data:image/png;base64,YmFy\n\
+                                KEY;QUOTED-PRINTABLE:=66=6F=6F\n\
+                                SOUND:data:audio/basic,%66oo\n\
+                                URL:http://example.com/photo.jpg\n\
+                                NOTE:data is not a data URI\n\
+                                X-BAD:data:image/png;base64,Zm9vY\n\
+                                LABEL;ENCODING=7BIT:plain text\n\
+                                TITLE;ENCODING=X-UNKNOWN:Zm9v\n\
+                                END:VCARD\n").unwrap();
+    let data = |name| card.get_only(name).unwrap().binary_data();
+
+    assert_eq!(data("PHOTO").unwrap().unwrap(), b"foo");
+    assert_eq!(data("LOGO").unwrap().unwrap(), b"bar");
+    assert_eq!(data("KEY").unwrap().unwrap(), b"foo");
+    assert_eq!(data("SOUND").unwrap().unwrap(), b"foo");
+    assert!(data("URL").is_none());
+    assert!(data("NOTE").is_none());
+    assert_eq!(data("X-BAD").unwrap(), Err(DecodeError::InvalidBase64));
+    assert!(data("LABEL").is_none());
+    assert!(data("TITLE").is_none());
+
+    let cal = parse_component("BEGIN:VEVENT\n\
+                               ATTACH;FMTTYPE=text/plain;ENCODING=BASE64;VALUE=BINARY:aGVsbG8=\n\
+                               END:VEVENT\n").unwrap();
+    assert_eq!(cal.get_only("ATTACH").unwrap().binary_data().unwrap().unwrap(), b"hello");
+}
+
+#[test]
+fn test_rename_prop() {
+    let mut card = parse_component("BEGIN:VCARD\n\
+                                    FN:Erika\n\
+                                    X-OLD;TYPE=a:1\n\
+                                    NOTE:x\n\
+                                    x-old:2\n\
+                                    X-NEW:0\n\
+                                    END:VCARD\n").unwrap();
+
+    assert_eq!(card.rename_prop("x-Old", "X-NEW"), 2);
+    assert!(card.get_all("X-OLD").is_empty());
+    let new = card.get_all_values("X-NEW");
+    assert_eq!(new, ["0", "1", "2"]);
+    assert_eq!(card.get_all("X-NEW")[1].get_param("TYPE"), Some("a"));
+
+    assert_eq!(card.rename_prop("NOTE", "X-Comment"), 1);
+    assert_eq!(write_component(&card),
+               "BEGIN:VCARD\r\n\
+                FN:Erika\r\n\
+                X-Comment:x\r\n\
+                X-NEW:0\r\n\
+                X-NEW;TYPE=a:1\r\n\
+                X-NEW:2\r\n\
+                END:VCARD\r\n");
+
+    assert_eq!(card.rename_prop("X-MISSING", "X-OTHER"), 0);
+    assert_eq!(card.rename_prop("fn", "FN"), 1);
+    assert_eq!(card.get_value("FN").unwrap(), "Erika");
+}
+
+#[test]
+fn test_walk() {
+    let cal = parse_component("BEGIN:VCALENDAR\n\
+                               PRODID:test\n\
+                               BEGIN:VEVENT\n\
+                               UID:1\n\
+                               ATTENDEE:mailto:erika@example.com\n\
+                               BEGIN:VALARM\n\
+                               ACTION:EMAIL\n\
+                               ATTENDEE:mailto:erika@example.com\n\
+                               END:VALARM\n\
+                               END:VEVENT\n\
+                               BEGIN:VTODO\n\
+                               UID:2\n\
+                               END:VTODO\n\
+                               VERSION:2.0\n\
+                               END:VCALENDAR\n").unwrap();
+
+    let walked = cal.walk()
+        .map(|(c, key, p)| format!("{}/{}:{}", c.name, key, p.raw_value))
+        .collect::<Vec<_>>();
+    assert_eq!(walked, [
+        "VCALENDAR/PRODID:test",
+        "VCALENDAR/VERSION:2.0",
+        "VEVENT/UID:1",
+        "VEVENT/ATTENDEE:mailto:erika@example.com",
+        "VALARM/ACTION:EMAIL",
+        "VALARM/ATTENDEE:mailto:erika@example.com",
+        "VTODO/UID:2",
+    ]);
+
+    let with_email = cal.walk().filter(|(_, _, p)| p.raw_value.contains("erika@")).count();
+    assert_eq!(with_email, 2);
+    assert_eq!(Component::new("VCARD").walk().count(), 0);
+}
+
+#[test]
+fn test_unknown_properties() {
+    let cal = parse_component("BEGIN:VCALENDAR\n\
+                               VERSION:2.0\n\
+                               PRODID:test\n\
+                               X-WR-CALNAME:Work\n\
+                               BEGIN:VEVENT\n\
+                               UID:1\n\
+                               SUMMMARY:Typo\n\
+                               x-custom:ok\n\
+                               COLOR:red\n\
+                               BEGIN:VALARM\n\
+                               ACTION:DISPLAY\n\
+                               summmary:Again\n\
+                               TRIGGERR:-PT5M\n\
+                               END:VALARM\n\
+                               END:VEVENT\n\
+                               END:VCALENDAR\n").unwrap();
+    assert_eq!(cal.unknown_properties(), ["SUMMMARY", "TRIGGERR"]);
+
+    let card = parse_component("BEGIN:VCARD\nVERSION:4.0\nFN:Erika\nitem1.tel:123\nEND:VCARD\n").unwrap();
+    assert!(card.unknown_properties().is_empty());
+}
+
+#[test]
+fn test_pref() {
+    let v4 = parse_component("BEGIN:VCARD\n\
+                              VERSION:4.0\n\
+                              TEL;PREF=1:1\n\
+                              TEL;PREF=100;TYPE=work:2\n\
+                              TEL;PREF=0:3\n\
+                              TEL;PREF=101:4\n\
+                              TEL;PREF=x:5\n\
+                              TEL:6\n\
+                              END:VCARD\n").unwrap();
+    let prefs = v4.get_all("TEL").iter().map(|p| p.pref()).collect::<Vec<_>>();
+    assert_eq!(prefs, [Some(1), Some(100), None, None, None, None]);
+
+    let v3 = parse_component("BEGIN:VCARD\n\
+                              VERSION:3.0\n\
+                              EMAIL;TYPE=INTERNET,pref:a@example.com\n\
+                              EMAIL;type=INTERNET;type=PREF:b@example.com\n\
+                              EMAIL;TYPE=INTERNET:c@example.com\n\
+                              TEL;WORK;PREF:123\n\
+                              END:VCARD\n").unwrap();
+    let prefs = v3.get_all("EMAIL").iter().map(|p| p.pref()).collect::<Vec<_>>();
+    assert_eq!(prefs, [Some(1), Some(1), None]);
+    assert_eq!(v3.get_only("TEL").unwrap().pref(), Some(1));
+
+    let mut card = v3.clone();
+    card.sort_props_by_pref("EMAIL");
+    assert_eq!(card.get_all("EMAIL")[2].raw_value, "c@example.com");
+}
+
+#[test]
+fn test_param_value_rfc6868_roundtrip() {
+    let input = "BEGIN:VEVENT\r\n\
+                 ATTENDEE;CN=\"George ^'Geo^' Herman^nRuth ^^ Babe; ^x ^\":mailto:babe@x.org\r\n\
+                 END:VEVENT\r\n";
+    let event = parse_component(input).unwrap();
+    let cn = "George \"Geo\" Herman\nRuth ^ Babe; ^x ^";
+    assert_eq!(event.get_only("ATTENDEE").unwrap().get_param("CN"), Some(cn));
+
+    // Written back, the literal carets are escaped, so the value reads back the same.
+    let written = write_component(&event);
+    assert_eq!(written,
+               "BEGIN:VEVENT\r\n\
+                ATTENDEE;CN=\"George ^'Geo^' Herman^nRuth ^^ Babe; ^^x ^^\":mailto:babe@x.org\r\n\
+                END:VEVENT\r\n");
+    assert_eq!(parse_component(&written).unwrap(), event);
+}
+
+#[test]
+fn test_count_subcomponents() {
+    let cal = parse_component("BEGIN:VCALENDAR\n\
+                               BEGIN:VEVENT\nDTSTART;VALUE=DATE:20240101\nEND:VEVENT\n\
+                               BEGIN:VEVENT\nDTSTART:20240101T090000Z\n\
+                               BEGIN:VALARM\nACTION:DISPLAY\nEND:VALARM\n\
+                               BEGIN:VALARM\nACTION:EMAIL\nEND:VALARM\n\
+                               END:VEVENT\n\
+                               BEGIN:VTODO\nEND:VTODO\n\
+                               END:VCALENDAR\n").unwrap();
+
+    let all_day = |c: &Component| {
+        c.name == "VEVENT" && c.get_only("DTSTART").is_some_and(|p| p.get_param("VALUE") == Some("DATE"))
+    };
+    assert_eq!(cal.count_subcomponents(all_day), 1);
+    assert_eq!(cal.count_subcomponents(|c| c.name == "VEVENT"), 2);
+    assert_eq!(cal.count_subcomponents(|c| c.name == "VALARM"), 0);
+    assert_eq!(cal.count_subcomponents_recursive(|c| c.name == "VALARM"), 2);
+    assert_eq!(cal.count_subcomponents_recursive(|_| true), 5);
+}
+
+#[test]
+fn test_component_into_iter() {
+    let cal = parse_component("BEGIN:VCALENDAR\n\
+                               VERSION:2.0\n\
+                               BEGIN:VEVENT\nUID:1\nEND:VEVENT\n\
+                               BEGIN:VTODO\nUID:2\nEND:VTODO\n\
+                               END:VCALENDAR\n").unwrap();
+
+    let mut names = vec![];
+    for sub in &cal {
+        names.push(&sub.name[..]);
+    }
+    assert_eq!(names, ["VEVENT", "VTODO"]);
+
+    let mut other = Component::new("VCALENDAR");
+    for sub in cal.clone() {
+        other.add_subcomponent(sub);
+    }
+    assert_eq!(other.subcomponents, cal.subcomponents);
+    assert_eq!(Component::new("VCARD").into_iter().count(), 0);
+}
+
+#[test]
+fn test_note_newline_escapes() {
+    let item = parse_component("BEGIN:VCARD\r\n\
+                                VERSION:4.0\r\n\
+                                NOTE:upper\\Nlower\\nend\r\n\
+                                END:VCARD\r\n").unwrap();
+    let note = item.get_only("NOTE").unwrap();
+    assert_eq!(note.value_as_string(), "upper\nlower\nend");
+
+    let mut out = Component::new("VCARD");
+    out.push(Property::new("NOTE", "upper\nlower\nend"));
+    assert!(write_component(&out).contains("NOTE:upper\\nlower\\nend\r\n"));
+}
+
+#[test]
+fn test_lowercase_begin_end() {
+    let item = parse_component("begin:vcard\r\n\
+                                FN:Erika Mustermann\r\n\
+                                Begin:x-sub\r\n\
+                                end:X-SUB\r\n\
+                                end:vcard\r\n").unwrap();
+    assert_eq!(item.name, "VCARD");
+    assert_eq!(item.get_only("FN").unwrap().raw_value, "Erika Mustermann");
+    assert_eq!(item.subcomponents.len(), 1);
+    assert_eq!(item.subcomponents[0].name, "X-SUB");
+}
+
+#[test]
+fn test_unfold_lines() {
+    use vobject::unfold;
+
+    let input = "BEGIN:VCARD\r\n\
+                 NOTE:first\r\n \x20second\r\n\t third\r\n\
+                 FN:Erika\r\n\
+                 END:VCARD\r\n";
+    let expected = vec!["BEGIN:VCARD", "NOTE:first second third", "FN:Erika", "END:VCARD"];
+
+    // With line endings kept, as from `BufRead::read_line()`.
+    let crlf = input.split_inclusive('\n').map(String::from);
+    assert_eq!(unfold(crlf).collect::<Vec<_>>(), expected);
+
+    // With LF line endings stripped, as from `BufRead::lines()`.
+    let lf = input.replace("\r\n", "\n");
+    assert_eq!(unfold(lf.lines().map(String::from)).collect::<Vec<_>>(), expected);
+
+    assert_eq!(unfold(vec![s!(" a")].into_iter()).collect::<Vec<_>>(), vec![" a"]);
+    assert_eq!(unfold(Vec::new().into_iter()).count(), 0);
+}
+
+#[test]
+fn test_retain_props() {
+    let input = "BEGIN:VCARD\r\n\
+                 VERSION:3.0\r\n\
+                 FN:Erika Mustermann\r\n\
+                 N:Mustermann;Erika;;;\r\n\
+                 TEL;TYPE=work:+49 221 9999123\r\n\
+                 EMAIL:erika@example.com\r\n\
+                 TEL;TYPE=home:+49 221 1234567\r\n\
+                 NOTE:private\r\n\
+                 BEGIN:X-SUB\r\n\
+                 FN:Nested\r\n\
+                 NOTE:also private\r\n\
+                 END:X-SUB\r\n\
+                 END:VCARD\r\n";
+    let keep = |name: &str, _: &Property| name == "FN" || name == "TEL";
+
+    let mut card = parse_component(input).unwrap();
+    card.retain_props(keep);
+    assert_eq!(card.props.keys().collect::<Vec<_>>(), vec!["FN", "TEL"]);
+    assert_eq!(card.get_all("TEL").len(), 2);
+    assert_eq!(card.subcomponents[0].props.len(), 2);
+
+    let mut card = parse_component(input).unwrap();
+    card.retain_props_recursive(keep);
+    assert_eq!(card.props.keys().collect::<Vec<_>>(), vec!["FN", "TEL"]);
+    assert_eq!(card.subcomponents[0].props.keys().collect::<Vec<_>>(), vec!["FN"]);
+
+    // The predicate also sees the property itself.
+    card.retain_props(|_, p| p.get_param("TYPE") != Some("home"));
+    assert_eq!(card.get_only("TEL").unwrap().raw_value, "+49 221 9999123");
+}
+
+#[test]
+fn test_icalendar_method_and_replies() {
+    use vobject::ICalendar;
+    use vobject::icalendar::Method;
+
+    let reply = ICalendar::build("BEGIN:VCALENDAR\r\n\
+                                  VERSION:2.0\r\n\
+                                  PRODID:-//Example//EN\r\n\
+                                  METHOD:reply\r\n\
+                                  BEGIN:VEVENT\r\n\
+                                  UID:meeting-1@example.com\r\n\
+                                  ORGANIZER:mailto:boss@example.com\r\n\
+                                  ATTENDEE;PARTSTAT=ACCEPTED;CN=Erika:mailto:erika@example.com\r\n\
+                                  ATTENDEE:mailto:max@example.com\r\n\
+                                  BEGIN:VALARM\r\n\
+                                  ACTION:EMAIL\r\n\
+                                  ATTENDEE;PARTSTAT=DECLINED:mailto:alarm@example.com\r\n\
+                                  END:VALARM\r\n\
+                                  END:VEVENT\r\n\
+                                  END:VCALENDAR\r\n").unwrap();
+    assert_eq!(reply.method(), Some(Method::Reply));
+    assert_eq!(Method::Reply.as_str(), "REPLY");
+
+    let responses = reply.attendee_responses();
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0].attendee.raw_value, "mailto:erika@example.com");
+    assert_eq!(responses[0].partstat, Some("ACCEPTED"));
+    assert_eq!(responses[0].component.name, "VEVENT");
+    assert_eq!(responses[1].attendee.raw_value, "mailto:max@example.com");
+    assert_eq!(responses[1].partstat, None);
+
+    let request = ICalendar::build("BEGIN:VCALENDAR\r\n\
+                                    METHOD:X-CUSTOM\r\n\
+                                    BEGIN:VEVENT\r\n\
+                                    ATTENDEE;PARTSTAT=ACCEPTED:mailto:erika@example.com\r\n\
+                                    END:VEVENT\r\n\
+                                    END:VCALENDAR\r\n").unwrap();
+    assert_eq!(request.method(), Some(Method::Other(s!("X-CUSTOM"))));
+    assert!(request.attendee_responses().is_empty());
+
+    assert_eq!(ICalendar::empty().method(), None);
+}
+
+#[test]
+fn test_utc_offset() {
+    let tz = parse_component("BEGIN:STANDARD\r\n\
+                              TZOFFSETFROM:-0400\r\n\
+                              TZOFFSETTO:+0530\r\n\
+                              X-OFFSET:+013045\r\n\
+                              END:STANDARD\r\n").unwrap();
+    assert_eq!(tz.get_only("TZOFFSETFROM").unwrap().value_as_utc_offset(), Some(-4 * 3600));
+    assert_eq!(tz.get_only("TZOFFSETTO").unwrap().value_as_utc_offset(), Some(5 * 3600 + 30 * 60));
+    assert_eq!(tz.get_only("X-OFFSET").unwrap().value_as_utc_offset(), Some(3600 + 30 * 60 + 45));
+    assert_eq!(Property::new("TZOFFSETTO", "-000015").value_as_utc_offset(), Some(-15));
+
+    assert_eq!(Property::new("TZOFFSETTO", "+0000").value_as_utc_offset(), Some(0));
+    assert_eq!(Property::new("TZOFFSETTO", "+235959").value_as_utc_offset(), Some(86399));
+
+    let out_of_range = ["+0099", "+0060", "+2375", "+2400", "+012399", "+012360", "-0000", "-000000"];
+    for invalid in out_of_range.iter().chain(&["", "0500", "+5", "+05:00", "+05000", "-abcd"]) {
+        assert_eq!(Property::new("TZOFFSETTO", *invalid).value_as_utc_offset(), None, "{:?} parsed", invalid);
+    }
+}
+
+#[test]
+fn test_attendees() {
+    use vobject::icalendar::Attendee;
+
+    let event = parse_component("BEGIN:VEVENT\r\n\
+                                 UID:meeting-1@example.com\r\n\
+                                 ORGANIZER;CN=\"Boss, The\":MAILTO:boss@example.com\r\n\
+                                 ATTENDEE;CN=Erika;ROLE=CHAIR;PARTSTAT=ACCEPTED;RSVP=TRUE:\r\n \
+                                 mailto:erika@example.com\r\n\
+                                 ATTENDEE;RSVP=maybe:urn:uuid:0c5d2c2f-7c8a-4b38-9b0d-e2e6c1e6c0a1\r\n\
+                                 END:VEVENT\r\n").unwrap();
+
+    assert_eq!(event.attendees(), vec![
+        Attendee {
+            address: s!("erika@example.com"),
+            cn: Some(s!("Erika")),
+            role: Some(s!("CHAIR")),
+            partstat: Some(s!("ACCEPTED")),
+            rsvp: Some(true),
+        },
+        Attendee {
+            address: s!("urn:uuid:0c5d2c2f-7c8a-4b38-9b0d-e2e6c1e6c0a1"),
+            cn: None,
+            role: None,
+            partstat: None,
+            rsvp: None,
+        },
+    ]);
+
+    let organizer = event.organizer().unwrap();
+    assert_eq!(organizer.address, "boss@example.com");
+    assert_eq!(organizer.cn.as_deref(), Some("Boss, The"));
+    assert_eq!(organizer.partstat, None);
+
+    assert!(Component::new("VEVENT").attendees().is_empty());
+    assert_eq!(Component::new("VEVENT").organizer(), None);
+}
+
+#[test]
+fn test_combine_type_params() {
+    let input = "BEGIN:VCARD\r\n\
+                 TEL;TYPE=work;TYPE=voice:+49 221 9999123\r\n\
+                 EMAIL;TYPE=home,pref:erika@example.com\r\n\
+                 END:VCARD\r\n";
+    let card = parse_component(input).unwrap();
+
+    // By default, the structure is kept as it was parsed.
+    assert_eq!(write_component(&card), input);
+
+    let options = WriteOptions { combine_type_params: true, ..WriteOptions::default() };
+    let combined = write_component_with(&card, &options);
+    assert_eq!(combined,
+               "BEGIN:VCARD\r\n\
+               TEL;TYPE=work,voice:+49 221 9999123\r\n\
+               EMAIL;TYPE=home,pref:erika@example.com\r\n\
+               END:VCARD\r\n");
+    assert_eq!(parse_component(&combined).unwrap(), card);
+    assert_eq!(write_component(&parse_component(&combined).unwrap()), combined);
+
+    // Bare vCard 2.1 types are occurrences of TYPE of their own.
+    let options = ParseOptions { vcard21: true, ..ParseOptions::default() };
+    let card = parse_component_with("BEGIN:VCARD\r\nTEL;WORK;VOICE:123\r\nEND:VCARD\r\n", &options).unwrap();
+    assert!(write_component(&card).contains("TEL;TYPE=WORK;TYPE=VOICE:123\r\n"));
+    let options = WriteOptions { combine_type_params: true, ..WriteOptions::default() };
+    assert!(write_component_with(&card, &options).contains("TEL;TYPE=WORK,VOICE:123\r\n"));
+}
+
+#[test]
+fn test_merge_changed_fn() {
+    use vobject::MergeStrategy;
+
+    let card = |version: &str, name: &str| parse_component(&format!(
+            "BEGIN:VCARD\n\
+            VERSION:{}\n\
+            UID:erika\n\
+            N:Mustermann;Erika;;;\n\
+            FN:{}\n\
+            END:VCARD\n", version, name)).unwrap();
+
+    // FN may occur only once in vCard 3.0, so a changed one replaces the old one.
+    let mut local = card("3.0", "Erika Mustermann");
+    let summary = local.merge(card("3.0", "Erika Gabler"), MergeStrategy::default());
+    assert_eq!(summary.replaced, vec![s!("FN")]);
+    assert_eq!(local.get_all_values("FN"), vec!["Erika Gabler"]);
+    assert!(local.validate().is_ok());
+
+    // vCard 4.0 allows several FNs, such as one per language, so they are combined.
+    let mut local = card("4.0", "Erika Mustermann");
+    let summary = local.merge(card("4.0", "Erika Gabler"), MergeStrategy::default());
+    assert_eq!(summary.added, vec![s!("FN")]);
+    assert_eq!(local.get_all_values("FN"), vec!["Erika Mustermann", "Erika Gabler"]);
+    assert!(local.validate().is_ok());
+
+    let mut twice = card("3.0", "Erika Mustermann");
+    twice.push(Property::new("FN", "Erika Gabler"));
+    assert!(twice.validate().is_err());
+}